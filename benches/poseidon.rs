@@ -0,0 +1,43 @@
+//! Poseidon benchmarks.
+//! cargo bench --bench poseidon
+use ark_bn254::Fr;
+use ark_std::{
+    rand::{rngs::StdRng, SeedableRng},
+    UniformRand,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use signature_schemes_ark::poseidon_hash::{
+    params::hasher::{poseidon_bn254_5x5::Params, RoundConstants},
+    sponge::PoseidonSponge,
+    Poseidon,
+};
+
+fn poseidon(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut group = c.benchmark_group("poseidon");
+
+    // Decoding the constants, which used to happen for every round of every permutation.
+    group.bench_function("decode_constants", |b| {
+        b.iter(RoundConstants::<5>::new::<Params>)
+    });
+
+    let inputs = [(); 5].map(|_| Fr::rand(&mut rng));
+    group.bench_function("permute", |b| {
+        b.iter(|| Poseidon::<5, Params>::new(inputs).permute())
+    });
+
+    for size in [5, 20] {
+        let inputs: Vec<Fr> = (0..size).map(|_| Fr::rand(&mut rng)).collect();
+        group.bench_with_input(BenchmarkId::new("sponge", size), &inputs, |b, inputs| {
+            b.iter(|| {
+                let mut sponge: PoseidonSponge = PoseidonSponge::new();
+                sponge.update(inputs);
+                sponge.squeeze()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, poseidon);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+//! Verification benchmarks. Run with and without the parallel feature to compare:
+//! cargo bench --bench verify
+//! cargo bench --bench verify --features parallel
+use ark_bn254::Fr;
+use ark_std::{
+    rand::{rngs::StdRng, SeedableRng},
+    UniformRand,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use signature_schemes_ark::signatures::{
+    bls_musig::{bls_musig_verify, AggregateSignature},
+    bls_single::{self, bls_batch_verify, bls_verify},
+    schnorr_single::{self, batch_verify, sch_verify},
+};
+
+/// Number of signatures in every batch.
+const BATCH_SIZES: [usize; 2] = [16, 64];
+
+fn schnorr_verify(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut group = c.benchmark_group("schnorr");
+
+    for size in BATCH_SIZES {
+        let batch: Vec<(schnorr_single::PublicKey, Fr, schnorr_single::SchSign)> = (0..size)
+            .map(|_| {
+                let signer = schnorr_single::Keypair::generate(&mut rng);
+                let tx_num = Fr::rand(&mut rng);
+                (*signer.public_key(), tx_num, signer.sign(tx_num, &mut rng))
+            })
+            .collect();
+
+        group.bench_with_input(BenchmarkId::new("sch_verify", size), &batch, |b, batch| {
+            b.iter(|| {
+                for (public_key, tx_num, signature) in batch {
+                    sch_verify(public_key, *tx_num, signature).unwrap();
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("batch_verify", size),
+            &batch,
+            |b, batch| b.iter(|| batch_verify(batch, &mut rng).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn bls_verify_many(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut group = c.benchmark_group("bls");
+    group.sample_size(10);
+
+    for size in BATCH_SIZES {
+        let batch: Vec<(bls_single::PublicKey, Fr, bls_single::BlsSig)> = (0..size)
+            .map(|_| {
+                let signer: bls_single::Keypair = bls_single::Keypair::generate(&mut rng);
+                let tx_num = Fr::rand(&mut rng);
+                (*signer.public_key(), tx_num, signer.sign(tx_num))
+            })
+            .collect();
+        let pairs: Vec<(bls_single::PublicKey, Fr)> = batch
+            .iter()
+            .map(|(public_key, tx_num, _)| (*public_key, *tx_num))
+            .collect();
+        let signatures: Vec<bls_single::BlsSig> = batch
+            .iter()
+            .map(|(_, _, signature)| signature.clone())
+            .collect();
+        let aggregate = AggregateSignature::aggregate(&signatures).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("bls_verify", size), &batch, |b, batch| {
+            b.iter(|| {
+                for (public_key, tx_num, signature) in batch {
+                    bls_verify(public_key, *tx_num, signature).unwrap();
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("bls_batch_verify", size),
+            &batch,
+            |b, batch| b.iter(|| bls_batch_verify(batch, &mut rng).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("bls_musig_verify", size),
+            &pairs,
+            |b, pairs| b.iter(|| bls_musig_verify(pairs, &aggregate).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, schnorr_verify, bls_verify_many);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+use std::fmt;
+
+/// Errors returned by signature verification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The verification equation does not hold.
+    InvalidSignature,
+    /// A point is not on the curve or not in the prime order subgroup.
+    MalformedPoint,
+    /// The public key is the identity element.
+    IdentityPublicKey,
+    /// No signers were given.
+    NoSigners,
+    /// The signer is not part of the signing group.
+    UnknownSigner,
+    /// The signer already contributed a different message in this round.
+    DuplicateSigner,
+    /// The partial signature of the signer at the given index is invalid.
+    InvalidPartialSignature {
+        /// Index or identifier of the signer in the signing group.
+        index: usize,
+    },
+    /// The secret share of the participant does not match the published commitments.
+    InvalidSecretShare {
+        /// Identifier of the participant that dealt the share.
+        index: usize,
+    },
+    /// The proof of knowledge of the participant's secret is invalid.
+    InvalidProofOfKnowledge {
+        /// Identifier of the participant that sent the proof.
+        index: usize,
+    },
+    /// Batch verification failed for the signatures at the given indices.
+    InvalidBatch {
+        /// Indices of the invalid signatures in the batch, in ascending order.
+        indices: Vec<usize>,
+    },
+    /// The same message appears twice in an aggregate where messages must be distinct.
+    DuplicateMessage,
+    /// The proof of possession of the private key is invalid.
+    InvalidProofOfPossession,
+    /// The signer's nonce commitments are missing from or differ in the signing package.
+    IncorrectCommitment,
+    /// The threshold is not between 2 and the number of participants.
+    InvalidThreshold,
+    /// The number of signers does not match the number of messages or signatures.
+    SignerCountMismatch {
+        /// Number of signers that was expected.
+        expected: usize,
+        /// Number of signers that was found.
+        found: usize,
+    },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::InvalidSignature => write!(f, "invalid signature"),
+            SignatureError::MalformedPoint => write!(f, "malformed curve point"),
+            SignatureError::IdentityPublicKey => write!(f, "public key is the identity"),
+            SignatureError::NoSigners => write!(f, "no signers given"),
+            SignatureError::UnknownSigner => write!(f, "signer is not in the signing group"),
+            SignatureError::DuplicateSigner => write!(f, "signer already contributed"),
+            SignatureError::InvalidPartialSignature { index } => {
+                write!(f, "invalid partial signature from signer {index}")
+            }
+            SignatureError::InvalidSecretShare { index } => {
+                write!(f, "invalid secret share from participant {index}")
+            }
+            SignatureError::InvalidProofOfKnowledge { index } => {
+                write!(f, "invalid proof of knowledge from participant {index}")
+            }
+            SignatureError::InvalidBatch { indices } => {
+                write!(f, "invalid signatures at indices {indices:?}")
+            }
+            SignatureError::DuplicateMessage => write!(f, "message is not distinct"),
+            SignatureError::InvalidProofOfPossession => write!(f, "invalid proof of possession"),
+            SignatureError::IncorrectCommitment => write!(f, "incorrect nonce commitment"),
+            SignatureError::InvalidThreshold => write!(f, "invalid threshold"),
+            SignatureError::SignerCountMismatch { expected, found } => {
+                write!(f, "expected {expected} signers, found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
@@ -0,0 +1,85 @@
+use sha2::{Digest, Sha256};
+
+/// Output size of SHA-256 in bytes.
+const B_IN_BYTES: usize = 32;
+/// Input block size of SHA-256 in bytes.
+const S_IN_BYTES: usize = 64;
+
+/// Expands the message into uniformly random bytes with SHA-256.
+/// More detailed explanation for expand_message_xmd:
+/// https://www.rfc-editor.org/rfc/rfc9380.html#section-5.3.1
+///
+/// Panics if the domain separation tag is longer than 255 bytes
+/// or more than 255 hash blocks are requested.
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Vec<u8> {
+    let ell = len_in_bytes.div_ceil(B_IN_BYTES);
+    assert!(ell <= 255, "Requested too many bytes");
+    assert!(dst.len() <= 255, "Domain separation tag is too long");
+
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let l_i_b_str = (len_in_bytes as u16).to_be_bytes();
+
+    // b_0 = H(Z_pad || msg || l_i_b_str || I2OSP(0, 1) || DST_prime)
+    let b_0 = Sha256::new()
+        .chain_update([0u8; S_IN_BYTES])
+        .chain_update(msg)
+        .chain_update(l_i_b_str)
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    // b_1 = H(b_0 || I2OSP(1, 1) || DST_prime)
+    let mut b_i = Sha256::new()
+        .chain_update(b_0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    let mut uniform_bytes = Vec::with_capacity(ell * B_IN_BYTES);
+    uniform_bytes.extend_from_slice(&b_i);
+
+    // b_i = H(strxor(b_0, b_(i - 1)) || I2OSP(i, 1) || DST_prime)
+    for i in 2..=ell {
+        let xored: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = Sha256::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+
+    uniform_bytes.truncate(len_in_bytes);
+    uniform_bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expand_message_xmd_sha256() {
+        // Test vectors from RFC 9380, Appendix K.1.
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+
+        let vectors: [(&[u8], &str); 3] = [
+            (
+                b"",
+                "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235",
+            ),
+            (
+                b"abc",
+                "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615",
+            ),
+            (
+                b"abcdef0123456789",
+                "eff31487c770a893cfb36f912fbfcbff40d5661771ca4b2cb4eafe524333f5c1",
+            ),
+        ];
+
+        for (msg, expected) in vectors {
+            let out = expand_message_xmd(msg, dst, 0x20);
+            assert_eq!(hex::encode(out), expected);
+        }
+    }
+}
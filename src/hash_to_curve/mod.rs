@@ -0,0 +1,189 @@
+/// Hashing arbitrary messages to BN254 points following the IETF hash-to-curve draft:
+/// https://www.rfc-editor.org/rfc/rfc9380.html
+pub mod expand_message;
+pub mod svdw;
+
+use self::{expand_message::expand_message_xmd, svdw::SvdwConfig};
+
+use ark_bn254::{g1, g2, G1Affine, G2Affine};
+use ark_ec::short_weierstrass::{Affine, Projective};
+use ark_ff::{Field, PrimeField};
+
+/// Number of bytes used to derive a single base field element.
+/// L = ceil((ceil(log2(p)) + k) / 8) with 128 bits of security.
+const L: usize = 48;
+
+/// Hashes the message into "count" field elements.
+/// More detailed explanation for hash_to_field:
+/// https://www.rfc-editor.org/rfc/rfc9380.html#section-5.2
+pub fn hash_to_field<F: Field>(msg: &[u8], dst: &[u8], count: usize) -> Vec<F> {
+    let m = F::extension_degree() as usize;
+    let uniform_bytes = expand_message_xmd(msg, dst, count * m * L);
+
+    uniform_bytes
+        .chunks(m * L)
+        .map(|element_bytes| {
+            let coefficients: Vec<F::BasePrimeField> = element_bytes
+                .chunks(L)
+                .map(F::BasePrimeField::from_be_bytes_mod_order)
+                .collect();
+            F::from_base_prime_field_elems(&coefficients).expect("Extension degree matches")
+        })
+        .collect()
+}
+
+/// Hashes the message onto a point in the prime order subgroup.
+/// Uses the random oracle construction, which adds the images of two field elements.
+pub fn hash_to_curve<P: SvdwConfig>(msg: &[u8], dst: &[u8]) -> Affine<P> {
+    let u = hash_to_field::<P::BaseField>(msg, dst, 2);
+    let q0 = P::map_to_curve(u[0]);
+    let q1 = P::map_to_curve(u[1]);
+    let r: Projective<P> = q0 + q1;
+    P::clear_cofactor_effective(&r)
+}
+
+/// Hashes the message onto a point in group G1.
+pub fn hash_to_g1(msg: &[u8], dst: &[u8]) -> G1Affine {
+    hash_to_curve::<g1::Config>(msg, dst)
+}
+
+/// Hashes the message onto a point in group G2.
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Affine {
+    hash_to_curve::<g2::Config>(msg, dst)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::{Fq, Fq2, Fr, G2Projective};
+    use ark_ec::{bn::BnConfig, CurveGroup, Group};
+    use ark_ff::{BigInteger, MontFp, Zero};
+    use ark_std::UniformRand;
+
+    #[test]
+    fn test_map_to_curve() {
+        let mut rng = crate::tests::test_rng();
+
+        // Exceptional case where the inverse is zero.
+        assert!(g1::Config::map_to_curve(Fq::zero()).is_on_curve());
+        assert!(g2::Config::map_to_curve(Fq2::zero()).is_on_curve());
+
+        for _ in 0..32 {
+            let u = Fq::rand(&mut rng);
+            let point = g1::Config::map_to_curve(u);
+            assert!(point.is_on_curve());
+            assert_eq!(g1::Config::sgn0(&u), g1::Config::sgn0(&point.y));
+
+            let u = Fq2::rand(&mut rng);
+            let point = g2::Config::map_to_curve(u);
+            assert!(point.is_on_curve());
+            assert_eq!(g2::Config::sgn0(&u), g2::Config::sgn0(&point.y));
+        }
+    }
+
+    #[test]
+    fn test_clear_cofactor_g2() {
+        // On the prime order subgroup psi acts as multiplication by q, so the
+        // effective cofactor is "x + 3x * q + x * q^2 + q^3" modulo the group order.
+        let q = Fr::from_le_bytes_mod_order(&Fq::MODULUS.to_bytes_le());
+        let x = Fr::from(ark_bn254::Config::X[0]);
+        let h_eff = x + Fr::from(3u64) * x * q + x * q.square() + q.square() * q;
+
+        let generator = G2Projective::generator();
+        let point = g2::Config::clear_cofactor_effective(&generator);
+        assert_eq!(point, (generator * h_eff).into_affine());
+    }
+
+    #[test]
+    fn test_hash_to_g1() {
+        // Vectors of the BN254G1_XMD:SHA-256_SVDW_RO_ suite.
+        let dst = b"QUUX-V01-CS02-with-BN254G1_XMD:SHA-256_SVDW_RO_";
+        let vectors: [(&[u8], Fq, Fq); 3] = [
+            (
+                b"",
+                MontFp!(
+                    "4790658965958450548702669593570794336562317867247372723806336874591549759110"
+                ),
+                MontFp!(
+                    "1163238807669877429342450210709044731909255047583162173012265677391336920021"
+                ),
+            ),
+            (
+                b"abc",
+                MontFp!(
+                    "16267524812466668166267883771992486438338357688076900798565538061554532963281"
+                ),
+                MontFp!(
+                    "1844916233815282837483764409618609279507070495361570126601873459268232811805"
+                ),
+            ),
+            (
+                b"abcdef0123456789",
+                MontFp!(
+                    "11077683243901808951859264683654586764079462418577485658911541848692394044746"
+                ),
+                MontFp!(
+                    "4858124309270455482359664916577923636817363175462672327824733704859450489677"
+                ),
+            ),
+        ];
+
+        for (msg, expected_x, expected_y) in vectors {
+            let point = hash_to_g1(msg, dst);
+            assert_eq!(point.x, expected_x);
+            assert_eq!(point.y, expected_y);
+        }
+
+        // Different domains give different points.
+        assert_ne!(hash_to_g1(b"", b"OTHER-DST"), hash_to_g1(b"", dst));
+    }
+
+    #[test]
+    fn test_hash_to_g2() {
+        // Vectors of the BN254G2_XMD:SHA-256_SVDW_RO_ suite, with the effective cofactor.
+        let dst = b"QUUX-V01-CS02-with-BN254G2_XMD:SHA-256_SVDW_RO_";
+        let vectors: [(&[u8], Fq2, Fq2); 3] = [
+            (
+                b"",
+                Fq2::new(
+                    MontFp!("7947280525355502288245767042139433332619084425813891508679326584140902765312"),
+                    MontFp!("10530141512348869141982713319207053343182583313484148698392330696376288318261"),
+                ),
+                Fq2::new(
+                    MontFp!("2079515028849057274649333561166551431956364880890028320215862191123161285080"),
+                    MontFp!("20169147323092870078028771345234445157617856249189458168875341276090072581620"),
+                ),
+            ),
+            (
+                b"abc",
+                Fq2::new(
+                    MontFp!("10305213714312555419584685236164610766057227018997600762219755820581571775698"),
+                    MontFp!("5140998983273781645596043003996621170933075714207210952317183701750931672829"),
+                ),
+                Fq2::new(
+                    MontFp!("12782657610222102886506935265351398708799194735435757564502179253917869011884"),
+                    MontFp!("15746452850775091549966312821847336261590899319279618339578671846526379873840"),
+                ),
+            ),
+            (
+                b"abcdef0123456789",
+                Fq2::new(
+                    MontFp!("9141649584568251133435811655082820452253999683001609355083509727807340928112"),
+                    MontFp!("19241337378620754008094815492162488101811979191715181531381201352430992486769"),
+                ),
+                Fq2::new(
+                    MontFp!("18149222514336885092356998491550186845822771992585824025266466238465484336696"),
+                    MontFp!("9129360097802525322055823374454170177267012396640126715240529872313988489338"),
+                ),
+            ),
+        ];
+
+        for (msg, expected_x, expected_y) in vectors {
+            let point = hash_to_g2(msg, dst);
+            assert!(point.is_on_curve());
+            assert!(point.is_in_correct_subgroup_assuming_on_curve());
+            assert_eq!(point.x, expected_x);
+            assert_eq!(point.y, expected_y);
+        }
+    }
+}
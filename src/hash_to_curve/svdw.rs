@@ -0,0 +1,154 @@
+use ark_bn254::{g1, g2, Fq, Fq2};
+use ark_ec::{
+    bn::BnConfig,
+    short_weierstrass::{Affine, Projective, SWCurveConfig},
+    CurveGroup, Group,
+};
+use ark_ff::{BigInteger, Field, MontFp, One, PrimeField, Zero};
+use std::sync::OnceLock;
+
+/// Constants "c1" to "c4" of the map, computed once per curve.
+#[derive(Clone, Debug)]
+pub struct SvdwConstants<F: Field> {
+    /// "c1 = g(Z)".
+    pub c1: F,
+    /// "c2 = -Z / 2".
+    pub c2: F,
+    /// "c3 = sqrt(-g(Z) * (3 * Z^2 + 4 * A))" with sgn0(c3) = 0.
+    pub c3: F,
+    /// "c4 = -4 * g(Z) / (3 * Z^2 + 4 * A)".
+    pub c4: F,
+}
+
+impl<F: Field> SvdwConstants<F> {
+    /// Computes the constants of the curve.
+    pub fn new<P: SvdwConfig<BaseField = F>>() -> Self {
+        let z = P::Z;
+        let a = P::COEFF_A;
+        let two = F::from(2u64);
+        let three = F::from(3u64);
+        let four = F::from(4u64);
+
+        let c1 = z.square() * z + a * z + P::COEFF_B;
+        let c2 = -z / two;
+        let mut c3 = (-c1 * (three * z.square() + four * a))
+            .sqrt()
+            .expect("Z is chosen so the constant is a square");
+        if P::sgn0(&c3) {
+            c3 = -c3;
+        }
+        let c4 = -four * c1 / (three * z.square() + four * a);
+
+        SvdwConstants { c1, c2, c3, c4 }
+    }
+}
+
+/// Shallue-van de Woestijne map for curves of the form y^2 = x^3 + A * x + B.
+/// More detailed explanation for the map:
+/// https://www.rfc-editor.org/rfc/rfc9380.html#section-6.6.1
+pub trait SvdwConfig: SWCurveConfig {
+    /// Non-zero constant "Z" of the map, chosen as in the draft's find_z_svdw.
+    const Z: Self::BaseField;
+
+    /// Returns the sign of the field element.
+    fn sgn0(x: &Self::BaseField) -> bool;
+
+    /// Returns the constants of the map, computed once and cached for all later calls.
+    fn constants() -> &'static SvdwConstants<Self::BaseField>;
+
+    /// Clears the cofactor, mapping the point into the prime order subgroup.
+    /// Defaults to multiplying by the cofactor of the curve.
+    fn clear_cofactor_effective(point: &Projective<Self>) -> Affine<Self> {
+        Self::clear_cofactor(&point.into_affine())
+    }
+
+    /// Maps the field element onto a point on the curve.
+    /// The point is not necessarily in the prime order subgroup.
+    fn map_to_curve(u: Self::BaseField) -> Affine<Self> {
+        let z = Self::Z;
+        let a = Self::COEFF_A;
+        let g = |x: Self::BaseField| x.square() * x + a * x + Self::COEFF_B;
+        let is_square = |x: Self::BaseField| x.is_zero() || x.legendre().is_qr();
+
+        // Constants of the map.
+        let SvdwConstants { c1, c2, c3, c4 } = *Self::constants();
+
+        let tv1 = u.square() * c1;
+        let tv2 = Self::BaseField::one() + tv1;
+        let tv1 = Self::BaseField::one() - tv1;
+        let tv3 = (tv1 * tv2).inverse().unwrap_or_else(Self::BaseField::zero);
+        let tv4 = u * tv1 * tv3 * c3;
+
+        // Candidate x coordinates, the first one with a square g(x) is taken.
+        let x1 = c2 - tv4;
+        let x2 = c2 + tv4;
+        let x3 = (tv2.square() * tv3).square() * c4 + z;
+        let x = if is_square(g(x1)) {
+            x1
+        } else if is_square(g(x2)) {
+            x2
+        } else {
+            x3
+        };
+
+        let mut y = g(x)
+            .sqrt()
+            .expect("One of the candidates always gives a square");
+        if Self::sgn0(&u) != Self::sgn0(&y) {
+            y = -y;
+        }
+
+        Affine::new_unchecked(x, y)
+    }
+}
+
+impl SvdwConfig for g1::Config {
+    const Z: Fq = MontFp!("1");
+
+    fn sgn0(x: &Fq) -> bool {
+        x.into_bigint().is_odd()
+    }
+
+    fn constants() -> &'static SvdwConstants<Fq> {
+        static CONSTANTS: OnceLock<SvdwConstants<Fq>> = OnceLock::new();
+        CONSTANTS.get_or_init(SvdwConstants::new::<Self>)
+    }
+}
+
+impl SvdwConfig for g2::Config {
+    const Z: Fq2 = Fq2::new(MontFp!("1"), MontFp!("0"));
+
+    fn sgn0(x: &Fq2) -> bool {
+        let sign_0 = x.c0.into_bigint().is_odd();
+        let zero_0 = x.c0.is_zero();
+        let sign_1 = x.c1.into_bigint().is_odd();
+        sign_0 || (zero_0 && sign_1)
+    }
+
+    fn constants() -> &'static SvdwConstants<Fq2> {
+        static CONSTANTS: OnceLock<SvdwConstants<Fq2>> = OnceLock::new();
+        CONSTANTS.get_or_init(SvdwConstants::new::<Self>)
+    }
+
+    /// Multiplies by the effective cofactor with the endomorphism psi, as in gnark-crypto:
+    /// `[x]P + psi([3x]P) + psi^2([x]P) + psi^3(P)`, where x is the BN parameter.
+    /// https://eprint.iacr.org/2017/419.pdf
+    fn clear_cofactor_effective(point: &Projective<Self>) -> Affine<Self> {
+        let x_point = point.mul_bigint(ark_bn254::Config::X);
+        let three_x_point = x_point.double() + x_point;
+
+        let result = x_point + psi(&three_x_point) + psi(&psi(&x_point)) + psi(&psi(&psi(point)));
+        result.into_affine()
+    }
+}
+
+/// Untwist-Frobenius-twist endomorphism of G2.
+fn psi(point: &Projective<g2::Config>) -> Projective<g2::Config> {
+    let mut result = *point;
+    result.x.frobenius_map_in_place(1);
+    result.x *= ark_bn254::Config::TWIST_MUL_BY_Q_X;
+    result.y.frobenius_map_in_place(1);
+    result.y *= ark_bn254::Config::TWIST_MUL_BY_Q_Y;
+    result.z.frobenius_map_in_place(1);
+    result
+}
@@ -1,3 +1,5 @@
-mod poseidon_hash;
-mod signatures;
+pub mod error;
+pub mod hash_to_curve;
+pub mod poseidon_hash;
+pub mod signatures;
 mod tests;
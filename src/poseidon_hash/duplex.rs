@@ -0,0 +1,291 @@
+use super::{
+    params::hasher::{poseidon_bn254_5x5::Params, RoundParams},
+    Poseidon,
+};
+
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use sha2::{Digest, Sha256};
+use std::marker::PhantomData;
+
+/// Flag of absorb calls in the encoded IO pattern.
+const ABSORB_FLAG: u32 = 0x8000_0000;
+
+/// Checks that the length of a call is non-zero and fits below the absorb flag.
+fn call_length(length: usize) -> u32 {
+    match u32::try_from(length) {
+        Ok(length) if length != 0 && length < ABSORB_FLAG => length,
+        _ => panic!("Call length must be non-zero and below 2^31"),
+    }
+}
+
+/// One call of the IO pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpongeOp {
+    /// Absorbs the given number of elements.
+    Absorb(u32),
+    /// Squeezes the given number of elements.
+    Squeeze(u32),
+}
+
+/// Duplex sponge following SAFE, the Sponge API for Field Elements:
+/// https://eprint.iacr.org/2023/522.pdf
+/// The absorb and squeeze calls are declared upfront in the IO pattern,
+/// which is hashed together with a domain separator into the capacity.
+/// Calls may be interleaved in any order, as long as they follow the pattern.
+/// The sponge of width `T` has rate `T - 1` and capacity 1.
+#[derive(Clone, Debug)]
+pub struct DuplexSponge<const T: usize = 5, P = Params> {
+    /// Internal state, with the rate first and the capacity last.
+    state: [Fr; T],
+    /// Position of the next absorbed element in the rate.
+    absorb_pos: usize,
+    /// Position of the next squeezed element in the rate.
+    squeeze_pos: usize,
+    /// Declared calls.
+    io_pattern: Vec<SpongeOp>,
+    /// Number of calls made so far.
+    io_count: usize,
+    _params: PhantomData<P>,
+}
+
+impl<const T: usize, P: RoundParams<T>> DuplexSponge<T, P> {
+    /// Number of elements absorbed or squeezed per permutation.
+    const RATE: usize = T - 1;
+
+    /// Starts the sponge for the IO pattern and domain separator.
+    ///
+    /// # Panics
+    /// Panics if a call length is zero or not below 2^31, also after aggregation.
+    pub fn new(io_pattern: &[SpongeOp], domain_separator: &[u8]) -> Self {
+        let mut state = [Fr::zero(); T];
+        state[Self::RATE] = Self::tag(io_pattern, domain_separator);
+        DuplexSponge {
+            state,
+            absorb_pos: 0,
+            // Forces a permutation before the first squeeze, so that it depends on the tag.
+            squeeze_pos: Self::RATE,
+            io_pattern: io_pattern.to_vec(),
+            io_count: 0,
+            _params: PhantomData,
+        }
+    }
+
+    /// Computes the tag from the encoded IO pattern and domain separator.
+    /// Consecutive calls of the same kind are aggregated, every call is encoded
+    /// as a big-endian 32-bit word with the highest bit set for absorb calls,
+    /// and the first 128 bits of the SHA-256 digest are taken as the tag.
+    fn tag(io_pattern: &[SpongeOp], domain_separator: &[u8]) -> Fr {
+        let mut calls: Vec<(bool, u32)> = Vec::new();
+        for op in io_pattern {
+            let (is_absorb, length) = match *op {
+                SpongeOp::Absorb(length) => (true, length),
+                SpongeOp::Squeeze(length) => (false, length),
+            };
+            let length = call_length(length as usize);
+            match calls.last_mut() {
+                Some((last_absorb, total)) if *last_absorb == is_absorb => {
+                    *total = total
+                        .checked_add(length)
+                        .map(|total| call_length(total as usize))
+                        .expect("Call length must be non-zero and below 2^31");
+                }
+                _ => calls.push((is_absorb, length)),
+            }
+        }
+        let words = calls.into_iter().map(|(is_absorb, length)| {
+            if is_absorb {
+                ABSORB_FLAG | length
+            } else {
+                length
+            }
+        });
+
+        let mut hasher = Sha256::new();
+        for word in words {
+            hasher.update(word.to_be_bytes());
+        }
+        hasher.update(domain_separator);
+        let digest = hasher.finalize();
+        Fr::from_be_bytes_mod_order(&digest[..16])
+    }
+
+    /// Checks the call against the IO pattern.
+    fn check_call(&mut self, op: SpongeOp) {
+        assert_eq!(
+            self.io_pattern.get(self.io_count),
+            Some(&op),
+            "Call does not follow the IO pattern"
+        );
+        self.io_count += 1;
+    }
+
+    /// Absorbs the elements into the rate, permuting whenever it is full.
+    ///
+    /// # Panics
+    /// Panics if the call is empty, too long or does not follow the IO pattern.
+    pub fn absorb(&mut self, inputs: &[Fr]) {
+        self.check_call(SpongeOp::Absorb(call_length(inputs.len())));
+
+        for input in inputs {
+            if self.absorb_pos == Self::RATE {
+                self.state = Poseidon::<T, P>::new(self.state).permute();
+                self.absorb_pos = 0;
+            }
+            self.state[self.absorb_pos] += input;
+            self.absorb_pos += 1;
+        }
+        // Forces a permutation before the next squeeze.
+        self.squeeze_pos = Self::RATE;
+    }
+
+    /// Squeezes `n` elements from the rate, permuting whenever it is used up.
+    ///
+    /// # Panics
+    /// Panics if the call is empty, too long or does not follow the IO pattern.
+    pub fn squeeze(&mut self, n: usize) -> Vec<Fr> {
+        self.check_call(SpongeOp::Squeeze(call_length(n)));
+
+        let mut outputs = Vec::with_capacity(n);
+        for _ in 0..n {
+            if self.squeeze_pos == Self::RATE {
+                self.state = Poseidon::<T, P>::new(self.state).permute();
+                self.squeeze_pos = 0;
+                self.absorb_pos = 0;
+            }
+            outputs.push(self.state[self.squeeze_pos]);
+            self.squeeze_pos += 1;
+        }
+        outputs
+    }
+
+    /// Finishes the sponge.
+    ///
+    /// # Panics
+    /// Panics if not all calls of the IO pattern were made.
+    pub fn finish(self) {
+        assert_eq!(
+            self.io_count,
+            self.io_pattern.len(),
+            "Calls of the IO pattern are missing"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_std::{test_rng, UniformRand};
+
+    type TestDuplex = DuplexSponge;
+    type TestPoseidon = Poseidon;
+
+    const PATTERN: [SpongeOp; 4] = [
+        SpongeOp::Absorb(2),
+        SpongeOp::Squeeze(1),
+        SpongeOp::Absorb(5),
+        SpongeOp::Squeeze(6),
+    ];
+
+    #[test]
+    fn test_duplex() {
+        let rng = &mut test_rng();
+        let inputs = [(); 7].map(|_| Fr::rand(rng));
+
+        let run = |domain_separator: &[u8]| {
+            let mut sponge = TestDuplex::new(&PATTERN, domain_separator);
+            sponge.absorb(&inputs[..2]);
+            let mut outputs = sponge.squeeze(1);
+            sponge.absorb(&inputs[2..]);
+            outputs.extend(sponge.squeeze(6));
+            sponge.finish();
+            outputs
+        };
+
+        let outputs = run(b"test");
+        assert_eq!(outputs.len(), 7);
+        assert_eq!(outputs, run(b"test"));
+        assert_ne!(outputs, run(b"other"));
+
+        // The first squeeze permutes the state with the absorbed inputs.
+        let mut state = [inputs[0], inputs[1], Fr::zero(), Fr::zero(), Fr::zero()];
+        state[TestDuplex::RATE] = TestDuplex::tag(&PATTERN, b"test");
+        assert_eq!(outputs[0], TestPoseidon::new(state).permute()[0]);
+    }
+
+    #[test]
+    fn test_squeeze_first() {
+        let squeeze = |domain_separator: &[u8]| {
+            let mut sponge = TestDuplex::new(&[SpongeOp::Squeeze(1)], domain_separator);
+            let output = sponge.squeeze(1);
+            sponge.finish();
+            output[0]
+        };
+        assert_ne!(squeeze(b"test"), squeeze(b"other"));
+
+        let mut state = [Fr::zero(); 5];
+        state[TestDuplex::RATE] = TestDuplex::tag(&[SpongeOp::Squeeze(1)], b"test");
+        assert_eq!(squeeze(b"test"), TestPoseidon::new(state).permute()[0]);
+    }
+
+    #[test]
+    fn test_tag_aggregates_calls() {
+        let split = [
+            SpongeOp::Absorb(1),
+            SpongeOp::Absorb(2),
+            SpongeOp::Squeeze(3),
+        ];
+        let joined = [SpongeOp::Absorb(3), SpongeOp::Squeeze(3)];
+        assert_eq!(
+            TestDuplex::tag(&split, b"test"),
+            TestDuplex::tag(&joined, b"test")
+        );
+        assert_ne!(
+            TestDuplex::tag(&joined, b"test"),
+            TestDuplex::tag(&[SpongeOp::Squeeze(3), SpongeOp::Absorb(3)], b"test")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Call length must be non-zero and below 2^31")]
+    fn test_duplex_zero_length() {
+        TestDuplex::new(&[SpongeOp::Absorb(1), SpongeOp::Squeeze(0)], b"test");
+    }
+
+    #[test]
+    #[should_panic(expected = "Call length must be non-zero and below 2^31")]
+    fn test_duplex_oversized_length() {
+        TestDuplex::new(&[SpongeOp::Absorb(ABSORB_FLAG)], b"test");
+    }
+
+    #[test]
+    #[should_panic(expected = "Call length must be non-zero and below 2^31")]
+    fn test_duplex_aggregated_overflow() {
+        TestDuplex::new(
+            &[SpongeOp::Squeeze(ABSORB_FLAG - 1), SpongeOp::Squeeze(1)],
+            b"test",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Call length must be non-zero and below 2^31")]
+    fn test_duplex_empty_call() {
+        let mut sponge = TestDuplex::new(&PATTERN, b"test");
+        sponge.absorb(&[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Call does not follow the IO pattern")]
+    fn test_duplex_wrong_call() {
+        let mut sponge = TestDuplex::new(&PATTERN, b"test");
+        sponge.squeeze(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Calls of the IO pattern are missing")]
+    fn test_duplex_missing_call() {
+        let mut sponge = TestDuplex::new(&PATTERN, b"test");
+        sponge.absorb(&[Fr::zero(), Fr::zero()]);
+        sponge.finish();
+    }
+}
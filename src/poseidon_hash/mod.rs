@@ -1,23 +1,36 @@
+/// Duplex sponge following SAFE
+pub mod duplex;
 /// Native sponge implementation
 pub mod params;
 pub mod sponge;
 
-use self::params::hasher::{poseidon_bn254_5x5::Params, RoundParams, Sbox};
+use self::{
+    params::hasher::{apply_matrix, poseidon_bn254_5x5::Params, RoundParams},
+    sponge::PoseidonSponge,
+};
 
 use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use std::marker::PhantomData;
 
-type P = Params;
-/// Constructs objects.
+/// Number of bytes packed into a single field element.
+const CHUNK_BYTES: usize = 31;
+
+/// Constructs objects for the permutation of width `T` with the parameters `P`.
 #[derive(Clone)]
-pub struct Poseidon {
+pub struct Poseidon<const T: usize = 5, P = Params> {
     /// Constructs an array for the inputs.
-    inputs: [Fr; 5],
+    inputs: [Fr; T],
+    _params: PhantomData<P>,
 }
 
-impl Poseidon {
+impl<const T: usize, P: RoundParams<T>> Poseidon<T, P> {
     /// Create the objects.
-    pub fn new(inputs: [Fr; 5]) -> Self {
-        Poseidon { inputs }
+    pub fn new(inputs: [Fr; T]) -> Self {
+        Poseidon {
+            inputs,
+            _params: PhantomData,
+        }
     }
 
     /// The Hades Design Strategy for Hashing.
@@ -26,29 +39,22 @@ impl Poseidon {
     /// More detailed explanation for
     /// The Round Function (TRF) and Hades:
     /// https://eprint.iacr.org/2019/458.pdf#page=5
-    pub fn permute(&self) -> [Fr; 5] {
-        let full_rounds = P::full_rounds();
-        let half_full_rounds = full_rounds / 2;
+    pub fn permute(&self) -> [Fr; T] {
+        let half_full_rounds = P::full_rounds() / 2;
         let partial_rounds = P::partial_rounds();
-        let round_constants = P::round_constants();
-        let total_count = P::round_constants_count();
-
-        let first_round_end = half_full_rounds * 5;
-        let first_round_constants = &round_constants[0..first_round_end];
 
-        let second_round_end = first_round_end + partial_rounds * 5;
-        let second_round_constants = &round_constants[first_round_end..second_round_end];
-
-        let third_round_constants = &round_constants[second_round_end..total_count];
+        // Constants are decoded once and shared by all permutations.
+        let constants = P::constants();
+        let (first_round_constants, rest) = constants.round_constants.split_at(half_full_rounds);
+        let third_round_constants = &rest[partial_rounds..];
 
         let mut state = self.inputs;
-        for round in 0..half_full_rounds {
-            let round_consts = P::load_round_constants(round, first_round_constants);
+        for round_consts in first_round_constants {
             // 1. step for the TRF.
             // AddRoundConstants step.
-            state = P::apply_round_constants(&state, &round_consts);
+            state = P::apply_round_constants(&state, round_consts);
             // Applying S-boxes for the full round.
-            for state in state.iter_mut().take(5) {
+            for state in state.iter_mut() {
                 // 2. step for the TRF.
                 // SubWords step.
                 *state = P::sbox_f(*state);
@@ -58,27 +64,29 @@ impl Poseidon {
             state = P::apply_mds(&state);
         }
 
-        for round in 0..partial_rounds {
-            let round_consts = P::load_round_constants(round, second_round_constants);
-            // 1. step for the TRF.
-            // AddRoundConstants step.
-            state = P::apply_round_constants(&state, &round_consts);
+        // Partial rounds use the equivalent form from the appendix B of the paper:
+        // the round constants are moved to the front and the MDS matrix is
+        // replaced by sparse matrices, with one dense matrix applied beforehand.
+        state = P::apply_round_constants(&state, &constants.partial_round_constants);
+        state = apply_matrix(&constants.pre_sparse_mds, &state);
+        for (round, sparse_mds) in constants.sparse_mds.iter().enumerate() {
             // Applying single S-box for the partial round.
-            // 2. step for the TRF.
             // SubWords step, denoted by S-box.
             state[0] = P::sbox_f(state[0]);
-            // 3. step for the TRF.
-            // MixLayer step.
-            state = P::apply_mds(&state);
+            // AddRoundConstants step, left for the first element only.
+            if let Some(round_const) = constants.partial_round_scalars.get(round) {
+                state[0] += round_const;
+            }
+            // MixLayer step with the sparse matrix.
+            state = sparse_mds.apply(&state);
         }
 
-        for round in 0..half_full_rounds {
-            let round_consts = P::load_round_constants(round, third_round_constants);
+        for round_consts in third_round_constants {
             // 1. step for the TRF.
             // AddRoundConstants step.
-            state = P::apply_round_constants(&state, &round_consts);
+            state = P::apply_round_constants(&state, round_consts);
             // Applying S-boxes for the full round.
-            for state in state.iter_mut().take(5) {
+            for state in state.iter_mut() {
                 // 2. step for the TRF.
                 // SubWords step, denoted by S-box.
                 *state = P::sbox_f(*state);
@@ -92,13 +100,39 @@ impl Poseidon {
     }
 }
 
+/// Hashes the inputs under the domain.
+/// Every call starts from a fresh sponge tagged with the domain,
+/// so the result only depends on the domain and the inputs.
+pub fn poseidon_hash(domain: &str, inputs: &[Fr]) -> Fr {
+    let mut sponge: PoseidonSponge = PoseidonSponge::new_tagged(domain);
+    sponge.update(inputs);
+    sponge.squeeze()
+}
+
+/// Hashes a fixed number of inputs under the domain, like [`poseidon_hash`].
+pub fn poseidon_hash_n<const N: usize>(domain: &str, inputs: [Fr; N]) -> Fr {
+    poseidon_hash(domain, &inputs)
+}
+
+/// Packs bytes into field elements, 31 little-endian bytes per element.
+/// The length is not encoded, so callers absorb it before the elements.
+pub(crate) fn bytes_to_field_elements(bytes: &[u8]) -> Vec<Fr> {
+    bytes
+        .chunks(CHUNK_BYTES)
+        .map(Fr::from_le_bytes_mod_order)
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::poseidon_hash::params::hasher::hex_to_field;
+    use crate::poseidon_hash::params::hasher::poseidon_bn254_3x5;
     use ark_bn254::Fr;
+    use ark_std::{test_rng, UniformRand};
 
     type TestPoseidon = Poseidon;
+    type TestPoseidon3 = Poseidon<3, poseidon_bn254_3x5::Params>;
 
     #[test]
     fn test_native_poseidon_5x5() {
@@ -110,7 +144,7 @@ mod test {
             "0x0000000000000000000000000000000000000000000000000000000000000003",
             "0x0000000000000000000000000000000000000000000000000000000000000004",
         ]
-        .map(|n| hex_to_field(n));
+        .map(hex_to_field);
 
         let outputs: [Fr; 5] = [
             "0x299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465",
@@ -119,7 +153,7 @@ mod test {
             "0x0eb08f6d809668a981c186beaf6110060707059576406b248e5d9cf6e78b3d3e",
             "0x07748bc6877c9b82c8b98666ee9d0626ec7f5be4205f79ee8528ef1c4a376fc7",
         ]
-        .map(|n| hex_to_field(n));
+        .map(hex_to_field);
 
         let poseidon = TestPoseidon::new(inputs);
 
@@ -127,4 +161,73 @@ mod test {
 
         assert_eq!(out, outputs);
     }
+
+    #[test]
+    fn test_native_poseidon_3x5() {
+        // Testing 3x5 input, the first output matches other Poseidon implementations
+        // of width 3 on BN254 such as circomlib.
+        let inputs: [Fr; 3] = [
+            "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "0x0000000000000000000000000000000000000000000000000000000000000001",
+            "0x0000000000000000000000000000000000000000000000000000000000000002",
+        ]
+        .map(hex_to_field);
+
+        let out = TestPoseidon3::new(inputs).permute();
+
+        assert_eq!(
+            out[0],
+            hex_to_field("0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+        );
+    }
+
+    /// Permutation without the optimized partial rounds.
+    fn permute_unoptimized<const T: usize, P: RoundParams<T>>(inputs: [Fr; T]) -> [Fr; T] {
+        let half_full_rounds = P::full_rounds() / 2;
+        let partial_rounds = P::partial_rounds();
+
+        let mut state = inputs;
+        for (round, round_consts) in P::constants().round_constants.iter().enumerate() {
+            state = P::apply_round_constants(&state, round_consts);
+            let is_partial = round >= half_full_rounds && round < half_full_rounds + partial_rounds;
+            for state in state.iter_mut().take(if is_partial { 1 } else { T }) {
+                *state = P::sbox_f(*state);
+            }
+            state = P::apply_mds(&state);
+        }
+        state
+    }
+
+    #[test]
+    fn test_poseidon_hash() {
+        let rng = &mut test_rng();
+        let inputs = [(); 3].map(|_| Fr::rand(rng));
+
+        // Hashes never carry state over from earlier calls.
+        let hash = poseidon_hash("domain", &inputs);
+        poseidon_hash("domain", &[Fr::rand(rng)]);
+        assert_eq!(poseidon_hash("domain", &inputs), hash);
+        assert_eq!(poseidon_hash_n("domain", inputs), hash);
+
+        // Domains and inputs are separated.
+        assert_ne!(poseidon_hash("other domain", &inputs), hash);
+        assert_ne!(poseidon_hash("domain", &inputs[..2]), hash);
+    }
+
+    #[test]
+    fn test_optimized_partial_rounds() {
+        let rng = &mut test_rng();
+        for _ in 0..10 {
+            let inputs = [(); 5].map(|_| Fr::rand(rng));
+            assert_eq!(
+                TestPoseidon::new(inputs).permute(),
+                permute_unoptimized::<5, Params>(inputs)
+            );
+            let inputs = [(); 3].map(|_| Fr::rand(rng));
+            assert_eq!(
+                TestPoseidon3::new(inputs).permute(),
+                permute_unoptimized::<3, poseidon_bn254_3x5::Params>(inputs)
+            );
+        }
+    }
 }
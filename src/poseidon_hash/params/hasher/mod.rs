@@ -1,59 +1,244 @@
+/// Poseidon Bn254 with WIDTH = 3 and EXPONENTIATION = 5
+pub mod poseidon_bn254_3x5;
 /// Poseidon Bn254 with 5 = 5 and EXPONENTIATION = 5
 pub mod poseidon_bn254_5x5;
 
 use ark_bn254::Fr;
-use ark_ff::{PrimeField, Zero};
+use ark_ff::{Field, One, PrimeField, Zero};
 use std::fmt::Debug;
 
-/// Trait definition of Round parameters of Poseidon
-pub trait RoundParams: Sbox + Clone + Debug {
+/// Round constants and MDS matrix of width `T` decoded from their hex form.
+#[derive(Clone, Debug)]
+pub struct RoundConstants<const T: usize> {
+    /// Round constants, one array for every round.
+    pub round_constants: Vec<[Fr; T]>,
+    /// MDS matrix with a size of T x T.
+    pub mds: [[Fr; T]; T],
+    /// Round constants added once before the optimized partial rounds.
+    pub partial_round_constants: [Fr; T],
+    /// Constants added to the first element after the S-box of every
+    /// optimized partial round, except the last one.
+    pub partial_round_scalars: Vec<Fr>,
+    /// Dense matrix applied once before the optimized partial rounds.
+    pub pre_sparse_mds: [[Fr; T]; T],
+    /// Sparse matrices replacing the MDS matrix of every partial round.
+    pub sparse_mds: Vec<SparseMds<T>>,
+}
+
+/// Sparse matrix "[[m_00, v], [w, I]]" of an optimized partial round,
+/// needing 2 * T - 1 multiplications instead of T * T.
+#[derive(Clone, Debug)]
+pub struct SparseMds<const T: usize> {
+    /// First row of the matrix.
+    pub row: [Fr; T],
+    /// First column of the matrix, starting with the same element as the row.
+    pub column: [Fr; T],
+}
+
+impl<const T: usize> SparseMds<T> {
+    /// Multiplies the state by the sparse matrix.
+    pub fn apply(&self, state: &[Fr; T]) -> [Fr; T] {
+        let mut new_state = *state;
+        new_state[0] = self.row.iter().zip(state).map(|(m, s)| *m * s).sum();
+        for (new_state, column) in new_state.iter_mut().zip(self.column).skip(1) {
+            *new_state += column * state[0];
+        }
+        new_state
+    }
+}
+
+impl<const T: usize> RoundConstants<T> {
+    /// Decodes the constants of the parameter set.
+    pub fn new<P: RoundParams<T>>() -> Self {
+        let round_constants = P::round_constants();
+        assert_eq!(
+            round_constants.len(),
+            (P::full_rounds() + P::partial_rounds()) * T,
+            "One constant per element of the state in every round"
+        );
+        let round_constants: Vec<[Fr; T]> = round_constants
+            .chunks_exact(T)
+            .map(|chunk| chunk.try_into().expect("Chunks have T elements"))
+            .collect();
+        let mds = P::mds();
+
+        let half_full_rounds = P::full_rounds() / 2;
+        let partial_rounds = P::partial_rounds();
+        let (partial_round_constants, partial_round_scalars) = Self::partial_round_constants(
+            &round_constants[half_full_rounds..half_full_rounds + partial_rounds],
+            &mds,
+        );
+        let (pre_sparse_mds, sparse_mds) = Self::sparse_mds(&mds, partial_rounds);
+
+        RoundConstants {
+            round_constants,
+            mds,
+            partial_round_constants,
+            partial_round_scalars,
+            pre_sparse_mds,
+            sparse_mds,
+        }
+    }
+
+    /// Moves the round constants of the partial rounds to the front, following
+    /// the appendix B of https://eprint.iacr.org/2019/458.pdf.
+    /// Constants of round i + 1 are carried through the MDS matrix into round i:
+    /// "M * x + c = M * (x + M^-1 * c)". Only the first element of "M^-1 * c" has to
+    /// stay after the S-box of round i, the rest is added to the constants of round i.
+    fn partial_round_constants(
+        partial_round_constants: &[[Fr; T]],
+        mds: &[[Fr; T]; T],
+    ) -> ([Fr; T], Vec<Fr>) {
+        let mds_inverse = invert(mds).expect("MDS matrix is invertible");
+
+        let mut constants = partial_round_constants.to_vec();
+        let mut scalars = Vec::with_capacity(constants.len().saturating_sub(1));
+        for round in (1..constants.len()).rev() {
+            let carried = apply_matrix(&mds_inverse, &constants[round]);
+            for i in 1..T {
+                constants[round - 1][i] += carried[i];
+            }
+            scalars.push(carried[0]);
+        }
+        scalars.reverse();
+
+        (constants[0], scalars)
+    }
+
+    /// Factors the MDS matrix of every partial round into a sparse matrix and
+    /// "M' = [[1, 0], [0, M_hat]]", which commutes with the partial S-box layer.
+    /// Starting from the last round, "M'" is moved into the previous round and
+    /// multiplied with its MDS matrix, leaving one dense matrix before the
+    /// partial rounds.
+    fn sparse_mds(mds: &[[Fr; T]; T], partial_rounds: usize) -> ([[Fr; T]; T], Vec<SparseMds<T>>) {
+        let mut sparse_mds = Vec::with_capacity(partial_rounds);
+        let mut matrix = *mds;
+        let mut pre_sparse_mds = identity();
+        for _ in 0..partial_rounds {
+            // Splits the matrix "[[m_00, v], [w, M_hat]]" into "M'".
+            pre_sparse_mds = identity();
+            for i in 1..T {
+                pre_sparse_mds[i][1..].copy_from_slice(&matrix[i][1..]);
+            }
+            let pre_sparse_mds_inverse =
+                invert(&pre_sparse_mds).expect("MDS submatrix is invertible");
+
+            // Sparse matrix "[[m_00, v * M_hat^-1], [w, I]]", where the first row
+            // is the first row of the matrix multiplied by "M'^-1".
+            let mut row = [Fr::zero(); T];
+            for (j, row) in row.iter_mut().enumerate() {
+                for i in 0..T {
+                    *row += matrix[0][i] * pre_sparse_mds_inverse[i][j];
+                }
+            }
+            let column = matrix.map(|matrix_row| matrix_row[0]);
+            sparse_mds.push(SparseMds { row, column });
+
+            // Dense part "M'" moved into the previous round.
+            matrix = multiply(&pre_sparse_mds, mds);
+        }
+        sparse_mds.reverse();
+
+        (pre_sparse_mds, sparse_mds)
+    }
+}
+
+/// Multiplies the state by the matrix.
+pub fn apply_matrix<const N: usize>(matrix: &[[Fr; N]; N], state: &[Fr; N]) -> [Fr; N] {
+    let mut new_state = [Fr::zero(); N];
+    for i in 0..N {
+        for j in 0..N {
+            new_state[i] += matrix[i][j] * state[j];
+        }
+    }
+    new_state
+}
+
+/// Returns the identity matrix.
+fn identity<const N: usize>() -> [[Fr; N]; N] {
+    let mut result = [[Fr::zero(); N]; N];
+    for (i, row) in result.iter_mut().enumerate() {
+        row[i] = Fr::one();
+    }
+    result
+}
+
+/// Multiplies two matrices.
+fn multiply<const N: usize>(a: &[[Fr; N]; N], b: &[[Fr; N]; N]) -> [[Fr; N]; N] {
+    let mut result = [[Fr::zero(); N]; N];
+    for i in 0..N {
+        for j in 0..N {
+            for k in 0..N {
+                result[i][j] += a[i][k] * b[k][j];
+            }
+        }
+    }
+    result
+}
+
+/// Inverts the matrix with Gauss-Jordan elimination.
+fn invert<const N: usize>(matrix: &[[Fr; N]; N]) -> Option<[[Fr; N]; N]> {
+    let mut matrix = *matrix;
+    let mut result = identity();
+    for column in 0..N {
+        let pivot = (column..N).find(|&row| !matrix[row][column].is_zero())?;
+        matrix.swap(column, pivot);
+        result.swap(column, pivot);
+
+        let pivot_inverse = matrix[column][column].inverse()?;
+        for j in 0..N {
+            matrix[column][j] *= pivot_inverse;
+            result[column][j] *= pivot_inverse;
+        }
+        for row in 0..N {
+            if row == column {
+                continue;
+            }
+            let factor = matrix[row][column];
+            for j in 0..N {
+                let (m, r) = (matrix[column][j], result[column][j]);
+                matrix[row][j] -= factor * m;
+                result[row][j] -= factor * r;
+            }
+        }
+    }
+    Some(result)
+}
+
+/// Trait definition of Round parameters of Poseidon with the width `T`
+pub trait RoundParams<const T: usize>: Sbox + Clone + Debug {
     /// Returns a number of full rounds.
     fn full_rounds() -> usize;
     /// Returns a number of partial rounds.
     fn partial_rounds() -> usize;
 
-    /// Returns total count size.
-    fn round_constants_count() -> usize {
-        let partial_rounds = Self::partial_rounds();
-        let full_rounds = Self::full_rounds();
-        (partial_rounds + full_rounds) * 5
-    }
+    /// Returns the constants, decoded once and cached for all later calls.
+    fn constants() -> &'static RoundConstants<T>;
 
-    /// Returns round constants array to be used in permutation.
+    /// Decodes round constants array from its hex string form.
     fn round_constants() -> Vec<Fr> {
         let round_constants_raw = Self::round_constants_raw();
-        let round_constants: Vec<Fr> = round_constants_raw
+        round_constants_raw
             .iter()
             .map(|x| hex_to_field(x))
-            .collect();
-        assert_eq!(round_constants.len(), Self::round_constants_count());
-        round_constants
-    }
-
-    /// Returns relevant constants for the given round.
-    fn load_round_constants(round: usize, round_consts: &[Fr]) -> [Fr; 5] {
-        let mut result = [Fr::zero(); 5];
-        for i in 0..5 {
-            result[i] = round_consts[round * 5 + i];
-        }
-        result
+            .collect()
     }
 
-    /// Returns MDS matrix with a size of 5 x 5.
-    fn mds() -> [[Fr; 5]; 5] {
+    /// Decodes MDS matrix with a size of T x T from its hex string form.
+    fn mds() -> [[Fr; T]; T] {
         let mds_raw = Self::mds_raw();
-        mds_raw.map(|row| row.map(|item| hex_to_field(item)))
+        mds_raw.map(|row| row.map(hex_to_field))
     }
 
     /// Returns round constants in its hex string form.
     fn round_constants_raw() -> Vec<&'static str>;
     /// Returns MDS martrix in its hex string form.
-    fn mds_raw() -> [[&'static str; 5]; 5];
+    fn mds_raw() -> [[&'static str; T]; T];
     /// Add round constants to the state values
     /// for the AddRoundConstants operation.
-    fn apply_round_constants(state: &[Fr; 5], round_consts: &[Fr; 5]) -> [Fr; 5] {
-        let mut next_state = [Fr::zero(); 5];
-        for i in 0..5 {
+    fn apply_round_constants(state: &[Fr; T], round_consts: &[Fr; T]) -> [Fr; T] {
+        let mut next_state = [Fr::zero(); T];
+        for i in 0..T {
             let state = state[i];
             let round_const = round_consts[i];
             let sum = state + round_const;
@@ -62,17 +247,8 @@ pub trait RoundParams: Sbox + Clone + Debug {
         next_state
     }
     /// Compute MDS matrix for MixLayer operation.
-    fn apply_mds(state: &[Fr; 5]) -> [Fr; 5] {
-        let mut new_state = [Fr::zero(); 5];
-        let mds = Self::mds();
-        for i in 0..5 {
-            for j in 0..5 {
-                let mds_ij = &mds[i][j];
-                let m_product = state[j] * mds_ij;
-                new_state[i] += m_product;
-            }
-        }
-        new_state
+    fn apply_mds(state: &[Fr; T]) -> [Fr; T] {
+        apply_matrix(&Self::constants().mds, state)
     }
 }
 
@@ -1,6 +1,7 @@
 use super::*;
 
 use ark_bn254::Fr;
+use std::sync::OnceLock;
 
 #[derive(Clone, Debug)]
 /// Configures a structure.
@@ -14,7 +15,7 @@ impl Sbox for Params {
     }
 }
 
-impl RoundParams for Params {
+impl RoundParams<5> for Params {
     fn partial_rounds() -> usize {
         60
     }
@@ -23,6 +24,11 @@ impl RoundParams for Params {
         8
     }
 
+    fn constants() -> &'static RoundConstants<5> {
+        static CONSTANTS: OnceLock<RoundConstants<5>> = OnceLock::new();
+        CONSTANTS.get_or_init(RoundConstants::new::<Self>)
+    }
+
     fn round_constants_raw() -> Vec<&'static str> {
         [
             "0x0eb544fee2815dda7f53e29ccac98ed7d889bb4ebd47c3864f3c2bd81a6da891",
@@ -1,36 +1,156 @@
-use super::Poseidon;
+use super::{
+    bytes_to_field_elements,
+    params::hasher::{poseidon_bn254_5x5::Params, RoundParams},
+    Poseidon,
+};
 
 use ark_bn254::Fr;
-use ark_ff::Zero;
+use ark_ff::{Field, One, Zero};
+use std::marker::PhantomData;
 
-/// Constructs objects.
+/// Absorption mode of the sponge, each mode being one version of the hashing rules.
+/// Hashes of different versions are domain separated and never compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpongeMode {
+    /// Version 1.
+    /// Inputs are absorbed into the first `rate` state elements, and the
+    /// remaining `width - rate` capacity elements are only changed by the permutation.
+    /// The first capacity element is initialized with the domain tag
+    /// "2^64 + (o - 1)" of variable length hashing with `o = 1` output,
+    /// see https://eprint.iacr.org/2019/458.pdf#page=8, plus "2^128 * version".
+    /// Inputs are padded injectively by appending a one and then zeros
+    /// up to a multiple of the rate, so `[a]`, `[a, 0]` and `[]` hash differently.
+    RateCapacity {
+        /// Number of elements absorbed per permutation, from 1 to `width - 1`.
+        rate: usize,
+    },
+    /// Version 0.
+    /// Chunks of `width` inputs are added to the whole state, leaving no capacity,
+    /// and the last chunk is padded with zeros, so `[a]`, `[a, 0]` and `[]`
+    /// hash to the same value. This is the behaviour of earlier versions, and it is kept only to check
+    /// hashes and signatures made with them. It lacks the security argument
+    /// of the sponge construction and must not be used for new hashes.
+    Compat,
+}
+
+impl SpongeMode {
+    /// Returns the default mode for the width, with capacity 1.
+    pub fn for_width(width: usize) -> Self {
+        SpongeMode::RateCapacity { rate: width - 1 }
+    }
+
+    /// Returns the number of elements absorbed per permutation.
+    pub fn rate(&self, width: usize) -> usize {
+        match self {
+            SpongeMode::RateCapacity { rate } => *rate,
+            SpongeMode::Compat => width,
+        }
+    }
+
+    /// Returns the number of capacity elements.
+    pub fn capacity(&self, width: usize) -> usize {
+        width - self.rate(width)
+    }
+
+    /// Returns the version of the hashing rules.
+    pub fn version(&self) -> u64 {
+        match self {
+            SpongeMode::RateCapacity { .. } => 1,
+            SpongeMode::Compat => 0,
+        }
+    }
+
+    /// Returns the domain tag of the first capacity element.
+    fn domain_tag(&self) -> Fr {
+        let shift = Fr::from(1u128 << 64);
+        shift + Fr::from(self.version()) * shift.square()
+    }
+}
+
+/// Constructs objects for the sponge of width `T` with the parameters `P`.
 #[derive(Clone, Debug)]
-pub struct PoseidonSponge {
+pub struct PoseidonSponge<const T: usize = 5, P = Params> {
     /// Constructs a vector for the inputs.
     inputs: Vec<Fr>,
     /// Internal state
-    state: [Fr; 5],
+    state: [Fr; T],
+    /// Absorption mode.
+    mode: SpongeMode,
+    _params: PhantomData<P>,
 }
 
-impl PoseidonSponge {
-    /// Create objects.
+impl<const T: usize, P: RoundParams<T>> PoseidonSponge<T, P> {
+    /// Create objects with the default mode of rate `T - 1` and capacity 1.
     pub fn new() -> Self {
+        Self::with_mode(SpongeMode::for_width(T))
+    }
+
+    /// Create objects with the given mode.
+    pub fn with_mode(mode: SpongeMode) -> Self {
+        let mut state = [Fr::zero(); T];
+        if let SpongeMode::RateCapacity { rate } = mode {
+            assert!((1..T).contains(&rate), "Rate must be from 1 to T - 1");
+            state[rate] = mode.domain_tag();
+        }
         Self {
             inputs: Vec::new(),
-            state: [Fr::zero(); 5],
+            state,
+            mode,
+            _params: PhantomData,
         }
     }
 
+    /// Create objects for a tagged hash with the default mode.
+    pub fn new_tagged(tag: &str) -> Self {
+        Self::new_tagged_with_mode(tag, SpongeMode::for_width(T))
+    }
+
+    /// Create objects for a tagged hash with the given mode.
+    /// Like BIP-340, the tag is hashed with its length and its digest
+    /// is absorbed twice before any other input.
+    pub fn new_tagged_with_mode(tag: &str, mode: SpongeMode) -> Self {
+        let mut tag_sponge = Self::with_mode(mode);
+        tag_sponge.update(&[Fr::from(tag.len() as u64)]);
+        tag_sponge.update(&bytes_to_field_elements(tag.as_bytes()));
+        let tag_hash = tag_sponge.squeeze();
+
+        let mut sponge = Self::with_mode(mode);
+        sponge.update(&[tag_hash, tag_hash]);
+        sponge
+    }
+
     /// Clones and appends all elements from a slice to the vec.
     pub fn update(&mut self, inputs: &[Fr]) {
         self.inputs.extend_from_slice(inputs);
     }
 
+    /// Returns the absorption mode.
+    pub fn mode(&self) -> SpongeMode {
+        self.mode
+    }
+
+    /// Pads the inputs according to the mode.
+    fn pad(&mut self) {
+        match self.mode {
+            SpongeMode::RateCapacity { rate } => {
+                // Appends "10*", so that no two inputs have the same padded form.
+                self.inputs.push(Fr::one());
+                let padded_len = self.inputs.len().next_multiple_of(rate);
+                self.inputs.resize(padded_len, Fr::zero());
+            }
+            SpongeMode::Compat => {
+                if self.inputs.is_empty() {
+                    self.inputs.push(Fr::zero());
+                }
+            }
+        }
+    }
+
     /// Absorb the data in and split it into
-    /// chunks of size 5.
-    fn load_state(chunk: &[Fr]) -> [Fr; 5] {
-        assert!(chunk.len() <= 5);
-        let mut fixed_chunk = [Fr::zero(); 5];
+    /// chunks of the rate size.
+    fn load_state(chunk: &[Fr]) -> [Fr; T] {
+        assert!(chunk.len() <= T);
+        let mut fixed_chunk = [Fr::zero(); T];
         fixed_chunk[..chunk.len()].copy_from_slice(chunk);
         fixed_chunk
     }
@@ -38,32 +158,187 @@ impl PoseidonSponge {
     /// Squeeze the data out by
     /// permuting until no more chunks are left.
     pub fn squeeze(&mut self) -> Fr {
-        if self.inputs.is_empty() {
-            self.inputs.push(Fr::zero());
+        self.absorb();
+        self.state[0]
+    }
+
+    /// Squeeze `n` elements out. The first `rate` elements are read from the
+    /// state after absorbing the inputs, and the state is permuted again
+    /// for every further `rate` elements.
+    /// The first element is the one returned by [`PoseidonSponge::squeeze`].
+    pub fn squeeze_many(&mut self, n: usize) -> Vec<Fr> {
+        self.absorb();
+
+        let rate = self.mode.rate(T);
+        let mut outputs = Vec::with_capacity(n);
+        loop {
+            let count = rate.min(n - outputs.len());
+            outputs.extend_from_slice(&self.state[..count]);
+            if outputs.len() == n {
+                break;
+            }
+            self.state = Poseidon::<T, P>::new(self.state).permute();
         }
+        outputs
+    }
+
+    /// Pads the pending inputs and absorbs them into the state.
+    fn absorb(&mut self) {
+        self.pad();
 
-        for chunk in self.inputs.chunks(5) {
-            let mut input = [Fr::zero(); 5];
+        for chunk in self.inputs.chunks(self.mode.rate(T)) {
+            let mut input = [Fr::zero(); T];
 
-            // Absorb
+            // Absorb, leaving the capacity elements untouched.
             let loaded_state = Self::load_state(chunk);
-            for i in 0..5 {
+            for i in 0..T {
                 input[i] = loaded_state[i] + self.state[i];
             }
 
             // Permute
-            let pos = Poseidon::new(input);
+            let pos = Poseidon::<T, P>::new(input);
             self.state = pos.permute();
         }
 
-        // Clear the inputs, and return the result
+        // Clear the inputs
         self.inputs.clear();
-        self.state[0]
     }
 }
 
-impl Default for PoseidonSponge {
+impl<const T: usize, P: RoundParams<T>> Default for PoseidonSponge<T, P> {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poseidon_hash::params::hasher::hex_to_field;
+    use crate::poseidon_hash::params::hasher::poseidon_bn254_3x5;
+    use ark_std::{test_rng, UniformRand};
+
+    type TestSponge = PoseidonSponge;
+    type TestPoseidon = Poseidon;
+
+    #[test]
+    fn test_compat_mode() {
+        let rng = &mut test_rng();
+        let inputs = [(); 5].map(|_| Fr::rand(rng));
+
+        // A single chunk is added to the zero state and permuted.
+        let mut sponge = TestSponge::with_mode(SpongeMode::Compat);
+        sponge.update(&inputs);
+        assert_eq!(sponge.squeeze(), TestPoseidon::new(inputs).permute()[0]);
+    }
+
+    #[test]
+    fn test_rate_capacity_mode() {
+        let rng = &mut test_rng();
+        let inputs = [(); 3].map(|_| Fr::rand(rng));
+        let domain_tag = SpongeMode::for_width(5).domain_tag();
+
+        // Three inputs and the padding fill the rate,
+        // and the capacity keeps the domain tag.
+        let mut sponge = TestSponge::new();
+        sponge.update(&inputs);
+        let state = [inputs[0], inputs[1], inputs[2], Fr::one(), domain_tag];
+        assert_eq!(sponge.squeeze(), TestPoseidon::new(state).permute()[0]);
+
+        // The capacity differs from the compat mode.
+        let mut compat = TestSponge::with_mode(SpongeMode::Compat);
+        let mut sponge = TestSponge::new();
+        compat.update(&state);
+        sponge.update(&state);
+        assert_ne!(sponge.squeeze(), compat.squeeze());
+
+        // Smaller rates are allowed as well.
+        let mut sponge = TestSponge::with_mode(SpongeMode::RateCapacity { rate: 2 });
+        sponge.update(&inputs[..1]);
+        let state = [inputs[0], Fr::one(), domain_tag, Fr::zero(), Fr::zero()];
+        assert_eq!(sponge.squeeze(), TestPoseidon::new(state).permute()[0]);
+    }
+
+    #[test]
+    fn test_width_3() {
+        let rng = &mut test_rng();
+        let input = Fr::rand(rng);
+
+        // Rate 2 and capacity 1 by default.
+        let mut sponge = PoseidonSponge::<3, poseidon_bn254_3x5::Params>::new();
+        sponge.update(&[input]);
+        let state = [input, Fr::one(), SpongeMode::for_width(3).domain_tag()];
+        let expected = Poseidon::<3, poseidon_bn254_3x5::Params>::new(state).permute();
+        assert_eq!(sponge.squeeze(), expected[0]);
+    }
+
+    #[test]
+    fn test_squeeze_many() {
+        let rng = &mut test_rng();
+        let inputs = [(); 6].map(|_| Fr::rand(rng));
+
+        let mut sponge = TestSponge::new();
+        sponge.update(&inputs);
+        let mut many_sponge = sponge.clone();
+        let first = sponge.squeeze();
+        let outputs = many_sponge.squeeze_many(10);
+        assert_eq!(outputs.len(), 10);
+        assert_eq!(outputs[0], first);
+
+        // Outputs beyond the rate come from one more permutation each.
+        let state = TestPoseidon::new(sponge.state).permute();
+        assert_eq!(outputs[..4], sponge.state[..4]);
+        assert_eq!(outputs[4..8], state[..4]);
+
+        // Shorter squeezes are a prefix of longer ones.
+        let mut sponge = TestSponge::new();
+        sponge.update(&inputs);
+        assert_eq!(sponge.squeeze_many(6), outputs[..6]);
+        assert!(TestSponge::new().squeeze_many(0).is_empty());
+    }
+
+    /// Hashes the inputs with the given mode.
+    fn hash(mode: SpongeMode, inputs: &[Fr]) -> Fr {
+        let mut sponge = TestSponge::with_mode(mode);
+        sponge.update(inputs);
+        sponge.squeeze()
+    }
+
+    #[test]
+    fn test_tag_length() {
+        // Trailing zero bytes of the tag are not lost in the packing.
+        let tagged = |tag: &str| {
+            let mut sponge: TestSponge = PoseidonSponge::new_tagged(tag);
+            sponge.update(&[Fr::one()]);
+            sponge.squeeze()
+        };
+        assert_ne!(tagged("x"), tagged("x\0"));
+        assert_ne!(tagged(""), tagged("\0"));
+    }
+
+    #[test]
+    fn test_injective_padding() {
+        let a = Fr::from(7u64);
+        let mode = SpongeMode::for_width(5);
+
+        let outputs = [
+            hash(mode, &[a]),
+            hash(mode, &[a, Fr::zero()]),
+            hash(mode, &[]),
+            hash(mode, &[Fr::zero()]),
+        ];
+        let expected = [
+            "0x0c6c02264ed1651c5686dfb759ae1efc9bbb962dd7d67266b418f242d4f5405f",
+            "0x1512acf28a5014527c1c3615cd206309a1bea54845c2aad929edf070c98cd93f",
+            "0x052fe5d8ccf90dfdf0f454a1ec0d1e3b2f55a9a2c0ae34a7d59c0945548aa645",
+            "0x15a10df13e10cc4caef20af23ee8f38ef7b297029e7a70bad1fcb45700d2e813",
+        ]
+        .map(hex_to_field);
+        assert_eq!(outputs, expected);
+
+        // The compat mode keeps the collisions of the zero padding.
+        let compat = SpongeMode::Compat;
+        assert_eq!(hash(compat, &[a]), hash(compat, &[a, Fr::zero()]));
+        assert_eq!(hash(compat, &[]), hash(compat, &[Fr::zero()]));
+    }
+}
@@ -1,76 +1,145 @@
 /// Signature scheme was made using https://2π.com/22/bls-signatures/
-use ark_bn254::{Bn254, Fr, G1Projective as G1, G2Projective as G2};
-use ark_ec::{pairing::Pairing, CurveGroup, Group};
-use ark_std::UniformRand;
+use ark_bn254::Fr;
+use ark_ec::{short_weierstrass::Projective, Group};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{borrow::Borrow, cfg_iter, collections::BTreeSet, marker::PhantomData};
+use derivative::Derivative;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-use crate::poseidon_hash::sponge::PoseidonSponge;
+use crate::{
+    error::SignatureError,
+    signatures::{
+        bls_single::{
+            hash_message, BasicScheme, BlsSig, BlsVariant, Ciphersuite, MinSig, PopScheme,
+            VerifiedPublicKey,
+        },
+        validate_point, validate_public_key,
+    },
+};
 
-pub struct BlsMuSig {
-    pub(crate) alice_pub: G2,
-    pub(crate) bob_pub: G2,
-    pub(crate) agg_sig: G1,
+/// Aggregated BLS signature "σ_agg = sum(σ_i)" of any number of signers.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    Clone(bound = ""),
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
+)]
+pub struct AggregateSignature<S: Ciphersuite = BasicScheme, V: BlsVariant = MinSig> {
+    pub(crate) agg_sig: Projective<V::SignatureConfig>,
+    pub(crate) ciphersuite: PhantomData<S>,
 }
 
-impl BlsMuSig {
-    pub fn sign(alice_msg: Fr, bob_msg: Fr) -> BlsMuSig {
-        // Random number generator.
-        let mut rng = ark_std::test_rng();
+impl<S: Ciphersuite, V: BlsVariant> AggregateSignature<S, V> {
+    /// Creates the empty aggregate, to which signatures are added one by one.
+    pub fn new() -> Self {
+        AggregateSignature {
+            agg_sig: Projective::zero(),
+            ciphersuite: PhantomData,
+        }
+    }
 
-        // Alice's private and public key generation.
-        let alice_priv = Fr::rand(&mut rng);
-        let alice_pub = G2::generator() * alice_priv;
+    /// Aggregates the signatures at once.
+    pub fn aggregate(
+        signatures: &[BlsSig<S, V>],
+    ) -> Result<AggregateSignature<S, V>, SignatureError> {
+        if signatures.is_empty() {
+            return Err(SignatureError::NoSigners);
+        }
 
-        // Bob's private and public key generation.
-        let bob_priv = Fr::rand(&mut rng);
-        let bob_pub = G2::generator() * bob_priv;
+        let mut aggregate = AggregateSignature::new();
+        for signature in signatures {
+            aggregate.add(signature);
+        }
+        Ok(aggregate)
+    }
 
-        // Both hashes their message "m".
-        let mut sponge = PoseidonSponge::new();
+    /// Adds one more signature to the aggregate.
+    pub fn add(&mut self, signature: &BlsSig<S, V>) {
+        self.agg_sig += signature.signature;
+    }
+}
 
-        sponge.update(&[alice_msg]);
-        let alice_msg_hash = PoseidonSponge::squeeze(&mut sponge);
+impl<S: Ciphersuite, V: BlsVariant> Default for AggregateSignature<S, V> {
+    fn default() -> Self {
+        AggregateSignature::new()
+    }
+}
 
-        sponge.update(&[bob_msg]);
-        let bob_msg_hash = PoseidonSponge::squeeze(&mut sponge);
+/// Verifies the aggregated signature over the (public key, message) pair of every signer.
+/// In the basic scheme the messages must be distinct, and in the proof of possession
+/// scheme the public keys must have a verified proof.
+pub fn bls_musig_verify<S: Ciphersuite, V: BlsVariant>(
+    signers: &[(S::AggregateKey<V>, Fr)],
+    sign: &AggregateSignature<S, V>,
+) -> Result<(), SignatureError> {
+    if signers.is_empty() {
+        return Err(SignatureError::NoSigners);
+    }
+    for (public_key, _) in signers {
+        validate_public_key(&public_key.borrow().0)?;
+    }
+    validate_point(&sign.agg_sig)?;
 
-        // Maps message "m" onto a point in group G2
-        let alice_msg_g1 = G1::generator() * alice_msg_hash;
-        let bob_msg_g1 = G1::generator() * bob_msg_hash;
+    // Signatures over the same message could be forged with a rogue key in the basic scheme.
+    if S::DISTINCT_MESSAGES {
+        let mut messages = BTreeSet::new();
+        if !signers.iter().all(|(_, message)| messages.insert(*message)) {
+            return Err(SignatureError::DuplicateMessage);
+        }
+    }
 
-        // Computes the aggregated signature.
-        let agg_sig = (alice_msg_g1 * alice_priv) + (bob_msg_g1 * bob_priv);
+    // Given a signature and the public keys, verifies that
+    // e(σ_agg, g) = e(H(m_1), pub_key_1) + ... + e(H(m_n), pub_key_n)
+    // by checking e(-σ_agg, g) + e(H(m_1), pub_key_1) + ... + e(H(m_n), pub_key_n) = 0
+    // with a single multi-pairing.
+    // Messages are hashed in parallel with the parallel feature.
+    let hashed_messages: Vec<Projective<V::SignatureConfig>> = cfg_iter!(signers)
+        .map(|(public_key, message)| hash_message::<S, V>(public_key.borrow(), *message))
+        .collect();
+    let signature_points = [-sign.agg_sig].into_iter().chain(hashed_messages);
+    let public_key_points = [Projective::generator()]
+        .into_iter()
+        .chain(signers.iter().map(|(public_key, _)| public_key.borrow().0));
 
-        BlsMuSig {
-            alice_pub,
-            bob_pub,
-            agg_sig,
-        }
+    if V::multi_pairing(signature_points, public_key_points).is_zero() {
+        Ok(())
+    } else {
+        Err(SignatureError::InvalidSignature)
     }
 }
 
-pub fn bls_musig_verify(alice_msg: Fr, bob_msg: Fr, sign: BlsMuSig) {
-    // Both hashes their message "m".
-    let mut sponge = PoseidonSponge::new();
+/// Verifies the aggregated signature of all signers over the same message.
+/// Only public keys with a verified proof of possession are accepted, otherwise
+/// a rogue key could cancel the other keys out of the aggregated public key.
+pub fn fast_aggregate_verify<V: BlsVariant>(
+    public_keys: &[VerifiedPublicKey<V>],
+    message: Fr,
+    sign: &AggregateSignature<PopScheme, V>,
+) -> Result<(), SignatureError> {
+    if public_keys.is_empty() {
+        return Err(SignatureError::NoSigners);
+    }
+    validate_point(&sign.agg_sig)?;
 
-    sponge.update(&[alice_msg]);
-    let alice_msg_hash = PoseidonSponge::squeeze(&mut sponge);
+    // Computes the aggregated public key "pub_key_agg = sum(pub_key_i)".
+    let agg_pub_key: Projective<V::PublicKeyConfig> =
+        public_keys.iter().map(|public_key| public_key.0 .0).sum();
+    validate_public_key(&agg_pub_key)?;
 
-    sponge.update(&[bob_msg]);
-    let bob_msg_hash = PoseidonSponge::squeeze(&mut sponge);
+    // Verifies that e(σ_agg, g) = e(H(m), pub_key_agg).
+    // The message does not depend on the public key in this scheme.
+    let signature_points = [
+        -sign.agg_sig,
+        hash_message::<PopScheme, V>(&public_keys[0].0, message),
+    ];
+    let public_key_points = [Projective::generator(), agg_pub_key];
 
-    // Given a signature and a public key, verifies that
-    // e(σ_agg ,g2) = e(H(m)_1, pub_key_1) + e(H(m)_2, pub_key_2).
-    if Bn254::pairing(&sign.agg_sig.into_affine(), &G2::generator().into_affine())
-        == Bn254::pairing(
-            &(G1::generator() * alice_msg_hash).into_affine(),
-            &sign.alice_pub.into_affine(),
-        ) + Bn254::pairing(
-            &(G1::generator() * bob_msg_hash).into_affine(),
-            &sign.bob_pub.into_affine(),
-        )
-    {
-        println!("Signature matches. They signed the message.")
+    if V::multi_pairing(signature_points, public_key_points).is_zero() {
+        Ok(())
     } else {
-        println!("Invalid Signature!")
+        Err(SignatureError::InvalidSignature)
     }
 }
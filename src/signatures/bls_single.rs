@@ -1,56 +1,458 @@
 /// Signature scheme was made using https://2π.com/22/bls-signatures/
-use ark_bn254::{Bn254, Fr, G1Projective as G1, G2Projective as G2};
-use ark_ec::{pairing::Pairing, Group};
-use ark_std::UniformRand;
+use ark_bn254::{g1, g2, Bn254, Fr};
+use ark_ec::{
+    pairing::{MillerLoopOutput, Pairing, PairingOutput},
+    short_weierstrass::{Projective, SWCurveConfig},
+    Group,
+};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{borrow::Borrow, marker::PhantomData};
+use ark_std::{
+    cfg_iter,
+    rand::{CryptoRng, RngCore},
+    UniformRand,
+};
+use derivative::Derivative;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-use crate::poseidon_hash::sponge::PoseidonSponge;
+use crate::{
+    error::SignatureError,
+    hash_to_curve::{hash_to_curve, svdw::SvdwConfig},
+    signatures::{find_invalid, split_prepared, validate_point, validate_public_key},
+};
 
-pub struct BlsSig {
-    pub(crate) alice_pub: G2,
-    pub(crate) signature: G1,
+/// Choice of the groups that hold public keys and signatures.
+/// Points of G1 are half the size of points of G2, so either the signatures
+/// or the public keys can be kept small.
+pub trait BlsVariant: Send + Sync + 'static {
+    /// Curve of the group that holds public keys.
+    type PublicKeyConfig: SWCurveConfig<ScalarField = Fr>;
+    /// Curve of the group that holds signatures and hashed messages.
+    type SignatureConfig: SvdwConfig<ScalarField = Fr>;
+
+    /// Name of the signature group in the ciphersuite ID.
+    const HASH_GROUP: &'static str;
+    /// Size of a compressed public key in bytes.
+    const PUBLIC_KEY_SIZE: usize;
+    /// Size of a compressed signature in bytes.
+    const SIGNATURE_SIZE: usize;
+
+    /// Computes the Miller loops of the pairings e(σ_i, pub_key_i), with the arguments
+    /// passed to the pairing in the order of the groups.
+    fn multi_miller_loop(
+        signature_points: impl IntoIterator<Item = Projective<Self::SignatureConfig>>,
+        public_key_points: impl IntoIterator<Item = Projective<Self::PublicKeyConfig>>,
+    ) -> MillerLoopOutput<Bn254>;
+
+    /// Computes the product of the pairings e(σ_i, pub_key_i) with a single final exponentiation.
+    fn multi_pairing(
+        signature_points: impl IntoIterator<Item = Projective<Self::SignatureConfig>>,
+        public_key_points: impl IntoIterator<Item = Projective<Self::PublicKeyConfig>>,
+    ) -> PairingOutput<Bn254> {
+        Bn254::final_exponentiation(Self::multi_miller_loop(signature_points, public_key_points))
+            .expect("Miller loop output is never zero")
+    }
+}
+
+/// Signatures in G1 and public keys in G2, which minimizes the signature size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MinSig;
+
+impl BlsVariant for MinSig {
+    type PublicKeyConfig = g2::Config;
+    type SignatureConfig = g1::Config;
+
+    const HASH_GROUP: &'static str = "BN254G1";
+    const PUBLIC_KEY_SIZE: usize = 64;
+    const SIGNATURE_SIZE: usize = 32;
+
+    fn multi_miller_loop(
+        signature_points: impl IntoIterator<Item = Projective<g1::Config>>,
+        public_key_points: impl IntoIterator<Item = Projective<g2::Config>>,
+    ) -> MillerLoopOutput<Bn254> {
+        Bn254::multi_miller_loop(signature_points, public_key_points)
+    }
+}
+
+/// Public keys in G1 and signatures in G2, which minimizes the public key size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MinPk;
+
+impl BlsVariant for MinPk {
+    type PublicKeyConfig = g1::Config;
+    type SignatureConfig = g2::Config;
+
+    const HASH_GROUP: &'static str = "BN254G2";
+    const PUBLIC_KEY_SIZE: usize = 32;
+    const SIGNATURE_SIZE: usize = 64;
+
+    fn multi_miller_loop(
+        signature_points: impl IntoIterator<Item = Projective<g2::Config>>,
+        public_key_points: impl IntoIterator<Item = Projective<g1::Config>>,
+    ) -> MillerLoopOutput<Bn254> {
+        Bn254::multi_miller_loop(public_key_points, signature_points)
+    }
+}
+
+/// BLS private key.
+#[derive(Clone)]
+pub struct SecretKey(pub(crate) Fr);
+
+impl SecretKey {
+    /// Wraps the scalar as a private key.
+    pub fn new(scalar: Fr) -> Self {
+        SecretKey(scalar)
+    }
+
+    /// Derives the public key in the public key group of the variant.
+    pub fn public_key<V: BlsVariant>(&self) -> PublicKey<V> {
+        PublicKey(Projective::generator() * self.0)
+    }
 }
 
-impl BlsSig {
-    pub fn sign(message: Fr) -> BlsSig {
-        // Random number generator.
-        let mut rng = ark_std::test_rng();
+/// BLS public key, in group G2 for the min-sig variant and in group G1 for the min-pk variant.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    Clone(bound = ""),
+    Copy(bound = ""),
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
+)]
+pub struct PublicKey<V: BlsVariant = MinSig>(pub(crate) Projective<V::PublicKeyConfig>);
 
-        // Alice's private and public key generation.
-        let alice_priv = Fr::rand(&mut rng);
-        let alice_pub = G2::generator() * alice_priv;
+impl<V: BlsVariant> PublicKey<V> {
+    /// Returns the public key point.
+    pub fn point(&self) -> Projective<V::PublicKeyConfig> {
+        self.0
+    }
 
-        // Hashes message "m".
-        let mut sponge = PoseidonSponge::new();
-        sponge.update(&[message]);
+    /// Verifies the proof of possession of the private key.
+    /// Only keys that passed this check can take part in same-message aggregation.
+    pub fn verify_pop(
+        &self,
+        proof: &ProofOfPossession<V>,
+    ) -> Result<VerifiedPublicKey<V>, SignatureError> {
+        validate_public_key(&self.0)?;
+        validate_point(&proof.proof)?;
 
-        let msg_hash = PoseidonSponge::squeeze(&mut sponge);
+        // Verifies that e(π, g) = e(H_pop(pub_key), pub_key).
+        let signature_points = [-proof.proof, hash_public_key(self)];
+        let public_key_points = [Projective::generator(), self.0];
 
-        // Maps message "m" onto a point in group G2.
-        let msg_g1 = G1::generator() * msg_hash;
+        if V::multi_pairing(signature_points, public_key_points).is_zero() {
+            Ok(VerifiedPublicKey(*self))
+        } else {
+            Err(SignatureError::InvalidProofOfPossession)
+        }
+    }
+}
+
+/// BLS public key whose proof of possession has been verified.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = ""),
+    Copy(bound = ""),
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
+)]
+pub struct VerifiedPublicKey<V: BlsVariant = MinSig>(pub(crate) PublicKey<V>);
+
+impl<V: BlsVariant> VerifiedPublicKey<V> {
+    /// Returns the public key.
+    pub fn public_key(&self) -> &PublicKey<V> {
+        &self.0
+    }
+}
+
+impl<V: BlsVariant> Borrow<PublicKey<V>> for VerifiedPublicKey<V> {
+    fn borrow(&self) -> &PublicKey<V> {
+        &self.0
+    }
+}
+
+/// Proof of possession "π = sk * H_pop(pub_key)", a signature over the public key
+/// under its own domain separation tag.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct ProofOfPossession<V: BlsVariant = MinSig> {
+    pub(crate) proof: Projective<V::SignatureConfig>,
+}
+
+/// BLS private and public key pair.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct Keypair<V: BlsVariant = MinSig> {
+    pub(crate) secret: SecretKey,
+    pub(crate) public: PublicKey<V>,
+}
+
+impl<V: BlsVariant> Keypair<V> {
+    /// Generates a fresh key pair.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Keypair<V> {
+        Keypair::from_secret_key(SecretKey(Fr::rand(rng)))
+    }
+
+    /// Builds the key pair for an existing private key.
+    pub fn from_secret_key(secret: SecretKey) -> Keypair<V> {
+        let public = secret.public_key();
+        Keypair { secret, public }
+    }
+
+    /// Returns the private key.
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.secret
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> &PublicKey<V> {
+        &self.public
+    }
+
+    /// Signs the message in the basic scheme.
+    pub fn sign(&self, message: Fr) -> BlsSig<BasicScheme, V> {
+        self.sign_with(message)
+    }
 
+    /// Signs the message in the given ciphersuite.
+    pub fn sign_with<S: Ciphersuite>(&self, message: Fr) -> BlsSig<S, V> {
         // Computes the signature.
-        let signature = msg_g1 * alice_priv;
+        let signature = hash_message::<S, V>(&self.public, message) * self.secret.0;
 
         BlsSig {
-            alice_pub,
             signature,
+            ciphersuite: PhantomData,
         }
     }
+
+    /// Proves possession of the private key by signing the public key.
+    pub fn prove_possession(&self) -> ProofOfPossession<V> {
+        let proof = hash_public_key(&self.public) * self.secret.0;
+
+        ProofOfPossession { proof }
+    }
+}
+
+/// BLS signature, tagged with the ciphersuite it was created in
+/// so signatures of different schemes can not be mixed.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    Clone(bound = ""),
+    Debug(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = "")
+)]
+pub struct BlsSig<S: Ciphersuite = BasicScheme, V: BlsVariant = MinSig> {
+    pub(crate) signature: Projective<V::SignatureConfig>,
+    pub(crate) ciphersuite: PhantomData<S>,
+}
+
+/// Scheme of the IETF BLS signature draft:
+/// https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-bls-signature-05#section-3
+/// The schemes differ in how aggregation of signatures over the same message
+/// is protected against rogue key attacks, which decides the checks that
+/// aggregate verification runs on top of validating every point:
+/// - basic: the messages must be pairwise distinct.
+/// - message augmentation: none, every signer signs its own "pub_key || m".
+/// - proof of possession: every public key must have a verified proof,
+///   which is enforced by the type of the keys.
+pub trait Ciphersuite: Send + Sync + 'static {
+    /// Name of the scheme at the end of the ciphersuite ID.
+    const SCHEME_ID: &'static str;
+
+    /// Whether aggregate verification rejects repeated messages.
+    const DISTINCT_MESSAGES: bool;
+
+    /// Public key accepted by aggregate verification in this scheme.
+    type AggregateKey<V: BlsVariant>: Borrow<PublicKey<V>> + Send + Sync;
+
+    /// Encodes the message that is hashed onto the signature group when signing
+    /// under the public key.
+    fn signing_input<V: BlsVariant>(_public_key: &PublicKey<V>, message: Fr) -> Vec<u8> {
+        let mut msg_bytes = Vec::new();
+        message
+            .serialize_compressed(&mut msg_bytes)
+            .expect("Serializing into a vector can not fail");
+        msg_bytes
+    }
+}
+
+/// Basic scheme, aggregation is only safe over distinct messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BasicScheme;
+
+impl Ciphersuite for BasicScheme {
+    const SCHEME_ID: &'static str = "NUL_";
+    const DISTINCT_MESSAGES: bool = true;
+
+    type AggregateKey<V: BlsVariant> = PublicKey<V>;
+}
+
+/// Message augmentation scheme, every signer signs "pub_key || m" so signatures of
+/// different signers are always over distinct messages, even when "m" is the same.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AugScheme;
+
+impl Ciphersuite for AugScheme {
+    const SCHEME_ID: &'static str = "AUG_";
+    const DISTINCT_MESSAGES: bool = false;
+
+    type AggregateKey<V: BlsVariant> = PublicKey<V>;
+
+    fn signing_input<V: BlsVariant>(public_key: &PublicKey<V>, message: Fr) -> Vec<u8> {
+        let mut msg_bytes = Vec::new();
+        public_key
+            .serialize_compressed(&mut msg_bytes)
+            .expect("Serializing into a vector can not fail");
+        message
+            .serialize_compressed(&mut msg_bytes)
+            .expect("Serializing into a vector can not fail");
+        msg_bytes
+    }
 }
 
-pub fn bls_verify(message: Fr, sign: BlsSig) {
-    // Hashes message "m"
-    let mut sponge = PoseidonSponge::new();
-    sponge.update(&[message]);
+/// Proof of possession scheme, every public key comes with a proof that its owner
+/// knows the private key, which allows fast aggregation over the same message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PopScheme;
 
-    let msg_hash = PoseidonSponge::squeeze(&mut sponge);
+impl Ciphersuite for PopScheme {
+    const SCHEME_ID: &'static str = "POP_";
+    const DISTINCT_MESSAGES: bool = false;
 
-    // Given a signature and a public key, verifies that e(σ, g2) = e(pub_key, H(m)).
-    if Bn254::pairing(&sign.signature, &G2::generator())
-        == Bn254::pairing(&(G1::generator() * msg_hash), &sign.alice_pub)
-    {
-        println!("Signature matches. Alice signed the message.")
+    type AggregateKey<V: BlsVariant> = VerifiedPublicKey<V>;
+}
+
+/// Domain separation tag for hashing messages onto the signature group,
+/// e.g. "BLS_SIG_BN254G1_XMD:SHA-256_SVDW_RO_NUL_" for the basic min-sig scheme.
+pub fn dst<S: Ciphersuite, V: BlsVariant>() -> Vec<u8> {
+    format!(
+        "BLS_SIG_{}_XMD:SHA-256_SVDW_RO_{}",
+        V::HASH_GROUP,
+        S::SCHEME_ID
+    )
+    .into_bytes()
+}
+
+/// Domain separation tag for hashing public keys onto the signature group in proofs of possession.
+pub fn pop_dst<V: BlsVariant>() -> Vec<u8> {
+    format!(
+        "BLS_POP_{}_XMD:SHA-256_SVDW_RO_{}",
+        V::HASH_GROUP,
+        PopScheme::SCHEME_ID
+    )
+    .into_bytes()
+}
+
+/// Hashes message "m" of the signer onto a point in the signature group.
+pub(crate) fn hash_message<S: Ciphersuite, V: BlsVariant>(
+    public_key: &PublicKey<V>,
+    message: Fr,
+) -> Projective<V::SignatureConfig> {
+    hash_to_curve(&S::signing_input(public_key, message), &dst::<S, V>()).into()
+}
+
+/// Hashes the compressed public key onto a point in the signature group.
+fn hash_public_key<V: BlsVariant>(public_key: &PublicKey<V>) -> Projective<V::SignatureConfig> {
+    let mut key_bytes = Vec::new();
+    public_key
+        .serialize_compressed(&mut key_bytes)
+        .expect("Serializing into a vector can not fail");
+
+    hash_to_curve(&key_bytes, &pop_dst::<V>()).into()
+}
+
+pub fn bls_verify<S: Ciphersuite, V: BlsVariant>(
+    public_key: &PublicKey<V>,
+    message: Fr,
+    sign: &BlsSig<S, V>,
+) -> Result<(), SignatureError> {
+    validate_public_key(&public_key.0)?;
+    validate_point(&sign.signature)?;
+
+    // Given a signature and a public key, verifies that e(σ, g) = e(H(m), pub_key)
+    // by checking e(-σ, g) + e(H(m), pub_key) = 0.
+    let signature_points = [-sign.signature, hash_message::<S, V>(public_key, message)];
+    let public_key_points = [Projective::generator(), public_key.0];
+
+    if V::multi_pairing(signature_points, public_key_points).is_zero() {
+        Ok(())
     } else {
-        println!("Invalid Signature!")
+        Err(SignatureError::InvalidSignature)
     }
 }
+
+/// Signature prepared for batch verification, with its message already hashed.
+struct BatchEntry<V: BlsVariant> {
+    public_key: Projective<V::PublicKeyConfig>,
+    hashed_message: Projective<V::SignatureConfig>,
+    signature: Projective<V::SignatureConfig>,
+}
+
+/// Verifies many signatures of different signers at once.
+/// Every signature is randomized with a small scalar "r_i" and the equations
+/// "e(σ_i, g) = e(H(m_i), pub_key_i)" are combined into
+/// "e(sum(r_i * σ_i), g) * prod(e(-r_i * H(m_i), pub_key_i)) = 1", which is checked with
+/// one multi Miller loop and a single final exponentiation.
+/// When the batch fails, it is split in halves until the invalid signatures are found,
+/// and their indices are returned.
+pub fn bls_batch_verify<S: Ciphersuite, V: BlsVariant, R: RngCore + CryptoRng>(
+    signatures: &[(PublicKey<V>, Fr, BlsSig<S, V>)],
+    rng: &mut R,
+) -> Result<(), SignatureError> {
+    // Signatures with malformed points are invalid without taking part in the batch.
+    // Messages are hashed in parallel with the parallel feature.
+    let prepared: Vec<Result<(usize, BatchEntry<V>), usize>> = cfg_iter!(signatures)
+        .enumerate()
+        .map(|(index, (public_key, message, signature))| {
+            validate_public_key(&public_key.0).map_err(|_| index)?;
+            validate_point(&signature.signature).map_err(|_| index)?;
+            let entry = BatchEntry::<V> {
+                public_key: public_key.0,
+                hashed_message: hash_message::<S, V>(public_key, *message),
+                signature: signature.signature,
+            };
+            Ok((index, entry))
+        })
+        .collect();
+    let (entries, mut invalid) = split_prepared(prepared);
+
+    find_invalid(&entries, &mut |batch| check_batch(batch, rng), &mut invalid);
+
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        invalid.sort_unstable();
+        Err(SignatureError::InvalidBatch { indices: invalid })
+    }
+}
+
+/// Checks the combined equation of the entries with one multi Miller loop
+/// and one final exponentiation.
+fn check_batch<V: BlsVariant, R: RngCore + CryptoRng>(
+    entries: &[(usize, BatchEntry<V>)],
+    rng: &mut R,
+) -> bool {
+    // 64-bit coefficients are enough to make a forged batch pass with negligible probability,
+    // and keep the scalar multiplications cheap.
+    let coefficients: Vec<Fr> = entries.iter().map(|_| Fr::from(rng.next_u64())).collect();
+
+    let agg_sig: Projective<V::SignatureConfig> = cfg_iter!(entries)
+        .zip(&coefficients)
+        .map(|((_, entry), r)| entry.signature * r)
+        .sum();
+    let randomized: Vec<Projective<V::SignatureConfig>> = cfg_iter!(entries)
+        .zip(&coefficients)
+        .map(|((_, entry), r)| -(entry.hashed_message * r))
+        .collect();
+
+    let signature_points = [agg_sig].into_iter().chain(randomized);
+    let public_key_points = [Projective::generator()]
+        .into_iter()
+        .chain(entries.iter().map(|(_, entry)| entry.public_key));
+
+    V::multi_pairing(signature_points, public_key_points).is_zero()
+}
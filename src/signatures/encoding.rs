@@ -0,0 +1,62 @@
+use ark_bn254::Fr;
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+
+/// Number of bytes packed into a single field element.
+const LIMB_BYTES: usize = 16;
+
+/// Canonical encoding of a point into field elements for hashing.
+/// The affine point is serialized in compressed form, which is the affine x
+/// coordinate with the sign of y and the point at infinity as flags, and
+/// the bytes are split into 128 bit little-endian limbs.
+/// Equal points always give the same elements and distinct points never do.
+pub fn point_to_field_elements<C: CurveGroup>(point: &C) -> Vec<Fr> {
+    let mut bytes = Vec::new();
+    point
+        .into_affine()
+        .serialize_compressed(&mut bytes)
+        .expect("Serializing into a vector can not fail");
+
+    bytes
+        .chunks(LIMB_BYTES)
+        .map(Fr::from_le_bytes_mod_order)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::{Fq, G1Projective as G1, G2Projective as G2};
+    use ark_ec::Group;
+    use ark_ff::Field;
+
+    #[test]
+    fn test_point_encoding() {
+        let point = G1::generator() * Fr::from(5u64);
+
+        // Same point in another projective representation.
+        let lambda = Fq::from(7u64);
+        let scaled = G1::new_unchecked(
+            point.x * lambda.square(),
+            point.y * lambda.square() * lambda,
+            point.z * lambda,
+        );
+        assert_eq!(point, scaled);
+        assert_ne!(point.x, scaled.x);
+        assert_eq!(
+            point_to_field_elements(&point),
+            point_to_field_elements(&scaled)
+        );
+
+        // Negated point shares the x coordinate but not the encoding.
+        assert_ne!(
+            point_to_field_elements(&point),
+            point_to_field_elements(&-point)
+        );
+
+        // G1 points take two field elements and G2 points take four.
+        assert_eq!(point_to_field_elements(&point).len(), 2);
+        assert_eq!(point_to_field_elements(&G2::generator()).len(), 4);
+    }
+}
@@ -1,4 +1,77 @@
 pub mod bls_musig;
 pub mod bls_single;
+pub mod encoding;
+pub mod schnorr_frost;
 pub mod schnorr_musig;
+pub mod schnorr_musig2;
 pub mod schnorr_single;
+pub mod transcript;
+
+/// Operating system backed random number generator for key generation and signing.
+#[cfg(feature = "std")]
+pub use ark_std::rand::rngs::OsRng;
+
+use crate::error::SignatureError;
+
+use ark_ec::{
+    short_weierstrass::{Projective, SWCurveConfig},
+    CurveGroup,
+};
+use ark_ff::Zero;
+
+/// Checks that the point is on the curve and in the prime order subgroup.
+pub(crate) fn validate_point<P: SWCurveConfig>(
+    point: &Projective<P>,
+) -> Result<(), SignatureError> {
+    let affine = point.into_affine();
+    if affine.is_on_curve() && affine.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(())
+    } else {
+        Err(SignatureError::MalformedPoint)
+    }
+}
+
+/// Checks that the public key is a valid point other than the identity.
+pub(crate) fn validate_public_key<P: SWCurveConfig>(
+    public_key: &Projective<P>,
+) -> Result<(), SignatureError> {
+    validate_point(public_key)?;
+    if public_key.is_zero() {
+        return Err(SignatureError::IdentityPublicKey);
+    }
+    Ok(())
+}
+
+/// Separates the prepared batch entries from the indices of malformed signatures.
+pub(crate) fn split_prepared<T>(prepared: Vec<Result<T, usize>>) -> (Vec<T>, Vec<usize>) {
+    let mut entries = Vec::with_capacity(prepared.len());
+    let mut invalid = Vec::new();
+    for item in prepared {
+        match item {
+            Ok(entry) => entries.push(entry),
+            Err(index) => invalid.push(index),
+        }
+    }
+    (entries, invalid)
+}
+
+/// Checks the entries as one batch and bisects failing batches, collecting invalid indices.
+pub(crate) fn find_invalid<T, F>(
+    entries: &[(usize, T)],
+    check_batch: &mut F,
+    invalid: &mut Vec<usize>,
+) where
+    F: FnMut(&[(usize, T)]) -> bool,
+{
+    if entries.is_empty() || check_batch(entries) {
+        return;
+    }
+    if let [(index, _)] = entries {
+        invalid.push(*index);
+        return;
+    }
+
+    let (left, right) = entries.split_at(entries.len() / 2);
+    find_invalid(left, check_batch, invalid);
+    find_invalid(right, check_batch, invalid);
+}
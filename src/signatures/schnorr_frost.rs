@@ -0,0 +1,734 @@
+/// Threshold signature scheme was made using https://www.rfc-editor.org/rfc/rfc9591.html
+use std::collections::BTreeMap;
+
+use ark_bn254::{Fr, G1Projective as G1};
+use ark_ec::Group;
+use ark_ff::{Field, One, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::{
+    io::Read,
+    rand::{CryptoRng, RngCore},
+    UniformRand,
+};
+
+use crate::{
+    error::SignatureError,
+    poseidon_hash::{poseidon_hash, poseidon_hash_n},
+    signatures::{
+        encoding::point_to_field_elements,
+        schnorr_single::{challenge, sch_verify, PublicKey, SchSign},
+        transcript::Transcript,
+        validate_point,
+    },
+};
+
+/// Tag of the hash that derives signing nonces.
+const NONCE_TAG: &str = "FROST/nonce";
+/// Tag of the hash that commits to the list of nonce commitments.
+const COMMITMENT_LIST_TAG: &str = "FROST/commitments";
+/// Protocol name of the transcript that derives binding factors.
+const BINDING_FACTOR_TAG: &str = "FROST/rho";
+/// Protocol name of the transcript that derives the challenge of the key generation proof of knowledge.
+const DKG_CHALLENGE_TAG: &str = "FROST/dkg";
+
+/// Non-zero identifier of a participant, used as its x coordinate for secret sharing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, CanonicalSerialize)]
+pub struct Identifier(u16);
+
+impl Valid for Identifier {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.0 == 0 {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for Identifier {
+    /// Zero is always refused, as it is the x coordinate of the group secret.
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let identifier = Identifier(u16::deserialize_with_mode(reader, compress, validate)?);
+        identifier.check()?;
+        Ok(identifier)
+    }
+}
+
+impl Identifier {
+    /// Creates the identifier, which must not be zero.
+    pub fn new(id: u16) -> Option<Identifier> {
+        (id != 0).then_some(Identifier(id))
+    }
+
+    /// Returns the identifier as a scalar.
+    fn to_scalar(self) -> Fr {
+        Fr::from(self.0)
+    }
+}
+
+impl From<Identifier> for usize {
+    fn from(identifier: Identifier) -> usize {
+        identifier.0 as usize
+    }
+}
+
+/// Feldman commitment "C_j = a_j * G" to the coefficients of a secret sharing polynomial.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct VssCommitment(pub(crate) Vec<G1>);
+
+impl VssCommitment {
+    /// Commits to the polynomial coefficients.
+    fn new(coefficients: &[Fr]) -> VssCommitment {
+        VssCommitment(coefficients.iter().map(|a| G1::generator() * a).collect())
+    }
+
+    /// Evaluates the committed polynomial "sum(C_j * x^j)" in the exponent.
+    fn evaluate(&self, identifier: Identifier) -> G1 {
+        let x = identifier.to_scalar();
+        self.0.iter().rev().fold(G1::zero(), |acc, c| acc * x + c)
+    }
+
+    /// Returns the commitment to the shared secret.
+    fn public_key(&self) -> G1 {
+        self.0[0]
+    }
+}
+
+/// Secret share "f(i)" of a participant along with the dealer's commitment.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SecretShare {
+    pub(crate) identifier: Identifier,
+    pub(crate) signing_share: Fr,
+    pub(crate) commitment: VssCommitment,
+}
+
+/// Long lived signing key of a participant.
+#[derive(Clone)]
+pub struct KeyPackage {
+    pub(crate) identifier: Identifier,
+    pub(crate) signing_share: Fr,
+    pub(crate) verifying_share: G1,
+    pub(crate) group_public_key: PublicKey,
+    pub(crate) min_signers: u16,
+}
+
+impl KeyPackage {
+    /// Verifies the secret share against the dealer's commitment.
+    pub fn new(share: SecretShare) -> Result<KeyPackage, SignatureError> {
+        // The commitment holds one point per coefficient, so its length is the threshold.
+        let min_signers = u16::try_from(share.commitment.0.len())
+            .map_err(|_| SignatureError::InvalidThreshold)?;
+        if min_signers < 2 {
+            return Err(SignatureError::InvalidThreshold);
+        }
+        for point in &share.commitment.0 {
+            validate_point(point)?;
+        }
+
+        let verifying_share = G1::generator() * share.signing_share;
+        if verifying_share != share.commitment.evaluate(share.identifier) {
+            return Err(SignatureError::InvalidSecretShare {
+                index: share.identifier.into(),
+            });
+        }
+
+        Ok(KeyPackage {
+            identifier: share.identifier,
+            signing_share: share.signing_share,
+            verifying_share,
+            group_public_key: PublicKey(share.commitment.public_key()),
+            min_signers,
+        })
+    }
+
+    /// Returns the participant's identifier.
+    pub fn identifier(&self) -> Identifier {
+        self.identifier
+    }
+
+    /// Returns the public key "Y_i = s_i * G" of the participant's share.
+    pub fn verifying_share(&self) -> G1 {
+        self.verifying_share
+    }
+
+    /// Returns the group public key.
+    pub fn group_public_key(&self) -> &PublicKey {
+        &self.group_public_key
+    }
+}
+
+/// Public keys of the group and of every participant's share.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicKeyPackage {
+    pub(crate) verifying_shares: BTreeMap<Identifier, G1>,
+    pub(crate) group_public_key: PublicKey,
+}
+
+impl PublicKeyPackage {
+    /// Derives the public keys from the commitments to all sharing polynomials.
+    fn from_commitments<'a>(
+        identifiers: impl Iterator<Item = Identifier>,
+        commitments: impl Iterator<Item = &'a VssCommitment> + Clone,
+    ) -> PublicKeyPackage {
+        let verifying_shares = identifiers
+            .map(|identifier| {
+                let share = commitments
+                    .clone()
+                    .map(|commitment| commitment.evaluate(identifier))
+                    .sum();
+                (identifier, share)
+            })
+            .collect();
+        let group_public_key = PublicKey(commitments.map(VssCommitment::public_key).sum());
+
+        PublicKeyPackage {
+            verifying_shares,
+            group_public_key,
+        }
+    }
+
+    /// Returns the public key of the participant's share.
+    pub fn verifying_share(&self, identifier: Identifier) -> Option<G1> {
+        self.verifying_shares.get(&identifier).copied()
+    }
+
+    /// Returns the group public key.
+    pub fn group_public_key(&self) -> &PublicKey {
+        &self.group_public_key
+    }
+}
+
+/// Checks that "2 <= min_signers <= max_signers".
+fn check_threshold(max_signers: u16, min_signers: u16) -> Result<(), SignatureError> {
+    if min_signers < 2 || min_signers > max_signers {
+        return Err(SignatureError::InvalidThreshold);
+    }
+    Ok(())
+}
+
+/// Returns the identifiers "1..=max_signers".
+fn identifiers(max_signers: u16) -> impl Iterator<Item = Identifier> + Clone {
+    (1..=max_signers).map(Identifier)
+}
+
+/// Evaluates the polynomial with the given coefficients at the identifier.
+fn evaluate_polynomial(coefficients: &[Fr], identifier: Identifier) -> Fr {
+    let x = identifier.to_scalar();
+    coefficients
+        .iter()
+        .rev()
+        .fold(Fr::zero(), |acc, a| acc * x + a)
+}
+
+/// Samples a polynomial of degree "min_signers - 1" with the given constant term.
+fn random_polynomial<R: RngCore + CryptoRng>(secret: Fr, min_signers: u16, rng: &mut R) -> Vec<Fr> {
+    let mut coefficients = vec![secret];
+    coefficients.extend((1..min_signers).map(|_| Fr::rand(rng)));
+    coefficients
+}
+
+/// Key generation by a trusted dealer who knows the group's private key.
+/// The dealer sends every secret share privately to its participant.
+pub fn trusted_dealer_keygen<R: RngCore + CryptoRng>(
+    max_signers: u16,
+    min_signers: u16,
+    rng: &mut R,
+) -> Result<(Vec<SecretShare>, PublicKeyPackage), SignatureError> {
+    check_threshold(max_signers, min_signers)?;
+
+    let coefficients = random_polynomial(Fr::rand(rng), min_signers, rng);
+    let commitment = VssCommitment::new(&coefficients);
+
+    let shares = identifiers(max_signers)
+        .map(|identifier| SecretShare {
+            identifier,
+            signing_share: evaluate_polynomial(&coefficients, identifier),
+            commitment: commitment.clone(),
+        })
+        .collect();
+    let public_key_package =
+        PublicKeyPackage::from_commitments(identifiers(max_signers), [&commitment].into_iter());
+
+    Ok((shares, public_key_package))
+}
+
+/// Message broadcast by every participant in the first round of key generation.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DkgRound1Package {
+    pub(crate) commitment: VssCommitment,
+    pub(crate) proof_big_r: G1,
+    pub(crate) proof_mu: Fr,
+}
+
+/// Secret state of a participant after the first round of key generation.
+pub struct DkgRound1Secret {
+    identifier: Identifier,
+    coefficients: Vec<Fr>,
+    commitment: VssCommitment,
+    max_signers: u16,
+}
+
+/// Message sent privately to a single participant in the second round of key generation.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DkgRound2Package {
+    pub(crate) signing_share: Fr,
+}
+
+/// Secret state of a participant after the second round of key generation.
+pub struct DkgRound2Secret {
+    identifier: Identifier,
+    own_share: Fr,
+    commitments: BTreeMap<Identifier, VssCommitment>,
+    max_signers: u16,
+}
+
+/// Challenge of the proof of knowledge of the participant's secret.
+fn dkg_challenge(identifier: Identifier, public_key: &G1, big_r: &G1) -> Fr {
+    let mut transcript = Transcript::new(DKG_CHALLENGE_TAG);
+    transcript.append_scalar("identifier", &identifier.to_scalar());
+    transcript.append_point("public key", public_key);
+    transcript.append_point("R", big_r);
+    transcript.challenge_scalar("challenge")
+}
+
+/// First round of distributed key generation without a trusted dealer.
+/// The participant samples its own sharing polynomial, commits to it and
+/// proves knowledge of its secret with a Schnorr proof.
+pub fn dkg_part1<R: RngCore + CryptoRng>(
+    identifier: Identifier,
+    max_signers: u16,
+    min_signers: u16,
+    rng: &mut R,
+) -> Result<(DkgRound1Secret, DkgRound1Package), SignatureError> {
+    check_threshold(max_signers, min_signers)?;
+    if identifier.0 > max_signers {
+        return Err(SignatureError::UnknownSigner);
+    }
+
+    let coefficients = random_polynomial(Fr::rand(rng), min_signers, rng);
+    let commitment = VssCommitment::new(&coefficients);
+
+    // Proves knowledge of "a_0" for the commitment "C_0".
+    let k = Fr::rand(rng);
+    let proof_big_r = G1::generator() * k;
+    let c = dkg_challenge(identifier, &commitment.public_key(), &proof_big_r);
+    let proof_mu = k + coefficients[0] * c;
+
+    let package = DkgRound1Package {
+        commitment: commitment.clone(),
+        proof_big_r,
+        proof_mu,
+    };
+    let secret = DkgRound1Secret {
+        identifier,
+        coefficients,
+        commitment,
+        max_signers,
+    };
+
+    Ok((secret, package))
+}
+
+/// Second round of distributed key generation.
+/// Verifies the proofs of all other participants and computes the secret share
+/// that has to be sent privately to each of them.
+pub fn dkg_part2(
+    secret: DkgRound1Secret,
+    round1_packages: &BTreeMap<Identifier, DkgRound1Package>,
+) -> Result<(DkgRound2Secret, BTreeMap<Identifier, DkgRound2Package>), SignatureError> {
+    let expected = secret.max_signers as usize - 1;
+    if round1_packages.len() != expected {
+        return Err(SignatureError::SignerCountMismatch {
+            expected,
+            found: round1_packages.len(),
+        });
+    }
+
+    let mut commitments = BTreeMap::new();
+    let mut round2_packages = BTreeMap::new();
+    for (&identifier, package) in round1_packages {
+        if identifier == secret.identifier || identifier.0 > secret.max_signers {
+            return Err(SignatureError::UnknownSigner);
+        }
+        if package.commitment.0.len() != secret.coefficients.len() {
+            return Err(SignatureError::InvalidThreshold);
+        }
+        for point in package.commitment.0.iter().chain([&package.proof_big_r]) {
+            validate_point(point)?;
+        }
+
+        // Verifies that "mu * G = R + c * C_0".
+        let c = dkg_challenge(
+            identifier,
+            &package.commitment.public_key(),
+            &package.proof_big_r,
+        );
+        if G1::generator() * package.proof_mu
+            != package.proof_big_r + package.commitment.public_key() * c
+        {
+            return Err(SignatureError::InvalidProofOfKnowledge {
+                index: identifier.into(),
+            });
+        }
+
+        commitments.insert(identifier, package.commitment.clone());
+        round2_packages.insert(
+            identifier,
+            DkgRound2Package {
+                signing_share: evaluate_polynomial(&secret.coefficients, identifier),
+            },
+        );
+    }
+    commitments.insert(secret.identifier, secret.commitment);
+
+    let round2_secret = DkgRound2Secret {
+        identifier: secret.identifier,
+        own_share: evaluate_polynomial(&secret.coefficients, secret.identifier),
+        commitments,
+        max_signers: secret.max_signers,
+    };
+
+    Ok((round2_secret, round2_packages))
+}
+
+/// Final round of distributed key generation.
+/// Verifies the received secret shares and sums them into the participant's signing share.
+pub fn dkg_part3(
+    secret: &DkgRound2Secret,
+    round2_packages: &BTreeMap<Identifier, DkgRound2Package>,
+) -> Result<(KeyPackage, PublicKeyPackage), SignatureError> {
+    let expected = secret.max_signers as usize - 1;
+    if round2_packages.len() != expected {
+        return Err(SignatureError::SignerCountMismatch {
+            expected,
+            found: round2_packages.len(),
+        });
+    }
+
+    let mut signing_share = secret.own_share;
+    for (identifier, package) in round2_packages {
+        let commitment = secret
+            .commitments
+            .get(identifier)
+            .filter(|_| *identifier != secret.identifier)
+            .ok_or(SignatureError::UnknownSigner)?;
+
+        // Verifies the share against the sender's commitment.
+        if G1::generator() * package.signing_share != commitment.evaluate(secret.identifier) {
+            return Err(SignatureError::InvalidSecretShare {
+                index: (*identifier).into(),
+            });
+        }
+        signing_share += package.signing_share;
+    }
+
+    let public_key_package = PublicKeyPackage::from_commitments(
+        identifiers(secret.max_signers),
+        secret.commitments.values(),
+    );
+    let min_signers = secret.commitments[&secret.identifier].0.len() as u16;
+    let key_package = KeyPackage {
+        identifier: secret.identifier,
+        signing_share,
+        verifying_share: G1::generator() * signing_share,
+        group_public_key: public_key_package.group_public_key,
+        min_signers,
+    };
+
+    Ok((key_package, public_key_package))
+}
+
+/// Commitments "(D_i, E_i)" to a participant's hiding and binding nonces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SigningCommitments {
+    pub(crate) hiding: G1,
+    pub(crate) binding: G1,
+}
+
+/// Secret hiding and binding nonces "(d_i, e_i)" of a participant.
+/// They are consumed by signing so they can never be used for two signatures.
+pub struct SigningNonces {
+    hiding: Fr,
+    binding: Fr,
+    commitments: SigningCommitments,
+}
+
+impl SigningNonces {
+    /// Derives a nonce from fresh randomness bound to the signing share.
+    fn nonce_generate<R: RngCore + CryptoRng>(signing_share: Fr, rng: &mut R) -> Fr {
+        poseidon_hash_n(NONCE_TAG, [Fr::rand(rng), signing_share])
+    }
+}
+
+/// First round of signing, generates the nonces and the commitments to publish.
+pub fn commit<R: RngCore + CryptoRng>(
+    key_package: &KeyPackage,
+    rng: &mut R,
+) -> (SigningNonces, SigningCommitments) {
+    let hiding = SigningNonces::nonce_generate(key_package.signing_share, rng);
+    let binding = SigningNonces::nonce_generate(key_package.signing_share, rng);
+    let commitments = SigningCommitments {
+        hiding: G1::generator() * hiding,
+        binding: G1::generator() * binding,
+    };
+
+    let nonces = SigningNonces {
+        hiding,
+        binding,
+        commitments,
+    };
+
+    (nonces, commitments)
+}
+
+/// Message and nonce commitments of the chosen signers, sent to each of them by the coordinator.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SigningPackage {
+    pub(crate) commitments: BTreeMap<Identifier, SigningCommitments>,
+    pub(crate) message: Fr,
+}
+
+impl SigningPackage {
+    /// Creates the package for the message.
+    pub fn new(commitments: BTreeMap<Identifier, SigningCommitments>, message: Fr) -> Self {
+        SigningPackage {
+            commitments,
+            message,
+        }
+    }
+
+    /// Computes the binding factor "rho_i" of every signer.
+    fn binding_factors(&self, group_public_key: &PublicKey) -> BTreeMap<Identifier, Fr> {
+        let mut commitment_list = Vec::new();
+        for (identifier, commitments) in &self.commitments {
+            commitment_list.push(identifier.to_scalar());
+            commitment_list.extend(point_to_field_elements(&commitments.hiding));
+            commitment_list.extend(point_to_field_elements(&commitments.binding));
+        }
+        let commitment_list_hash = poseidon_hash(COMMITMENT_LIST_TAG, &commitment_list);
+
+        self.commitments
+            .keys()
+            .map(|identifier| {
+                let mut transcript = Transcript::new(BINDING_FACTOR_TAG);
+                transcript.append_point("group public key", &group_public_key.0);
+                transcript.append_scalar("message", &self.message);
+                transcript.append_scalar("commitment list", &commitment_list_hash);
+                transcript.append_scalar("identifier", &identifier.to_scalar());
+                (*identifier, transcript.challenge_scalar("rho"))
+            })
+            .collect()
+    }
+
+    /// Computes the group commitment "R = sum(D_i + rho_i * E_i)".
+    fn group_commitment(&self, binding_factors: &BTreeMap<Identifier, Fr>) -> G1 {
+        self.commitments
+            .iter()
+            .map(|(identifier, commitments)| {
+                commitments.hiding + commitments.binding * binding_factors[identifier]
+            })
+            .sum()
+    }
+
+    /// Computes the Lagrange coefficient "lambda_i" of the signer at "x = 0".
+    fn lagrange_coefficient(&self, identifier: Identifier) -> Fr {
+        let x_i = identifier.to_scalar();
+        let (numerator, denominator) = self
+            .commitments
+            .keys()
+            .filter(|other| **other != identifier)
+            .fold((Fr::one(), Fr::one()), |(num, den), other| {
+                let x_j = other.to_scalar();
+                (num * x_j, den * (x_j - x_i))
+            });
+        numerator * denominator.inverse().expect("Identifiers are distinct")
+    }
+}
+
+/// Signature share "z_i" of a signer, sent back to the coordinator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SignatureShare {
+    pub(crate) share: Fr,
+}
+
+/// Values every signer and the coordinator derive from the signing package.
+struct SigningContext {
+    binding_factors: BTreeMap<Identifier, Fr>,
+    group_commitment: G1,
+    challenge: Fr,
+}
+
+impl SigningContext {
+    fn new(
+        signing_package: &SigningPackage,
+        group_public_key: &PublicKey,
+        min_signers: u16,
+    ) -> Result<SigningContext, SignatureError> {
+        let found = signing_package.commitments.len();
+        if found < min_signers as usize {
+            return Err(SignatureError::SignerCountMismatch {
+                expected: min_signers as usize,
+                found,
+            });
+        }
+        for commitments in signing_package.commitments.values() {
+            validate_point(&commitments.hiding)?;
+            validate_point(&commitments.binding)?;
+        }
+
+        let binding_factors = signing_package.binding_factors(group_public_key);
+        let group_commitment = signing_package.group_commitment(&binding_factors);
+        let challenge = challenge(
+            &group_commitment,
+            &group_public_key.0,
+            signing_package.message,
+        );
+
+        Ok(SigningContext {
+            binding_factors,
+            group_commitment,
+            challenge,
+        })
+    }
+}
+
+/// Second round of signing, computes "z_i = d_i + e_i * rho_i + lambda_i * s_i * c".
+pub fn sign(
+    signing_package: &SigningPackage,
+    nonces: SigningNonces,
+    key_package: &KeyPackage,
+) -> Result<SignatureShare, SignatureError> {
+    let own_commitments = signing_package
+        .commitments
+        .get(&key_package.identifier)
+        .ok_or(SignatureError::IncorrectCommitment)?;
+    if *own_commitments != nonces.commitments {
+        return Err(SignatureError::IncorrectCommitment);
+    }
+
+    let context = SigningContext::new(
+        signing_package,
+        &key_package.group_public_key,
+        key_package.min_signers,
+    )?;
+    let rho = context.binding_factors[&key_package.identifier];
+    let lambda = signing_package.lagrange_coefficient(key_package.identifier);
+
+    let share = nonces.hiding
+        + nonces.binding * rho
+        + lambda * key_package.signing_share * context.challenge;
+
+    Ok(SignatureShare { share })
+}
+
+/// Checks "z_i * G = D_i + rho_i * E_i + c * lambda_i * Y_i" for a single signer.
+fn verify_share(
+    identifier: Identifier,
+    share: &SignatureShare,
+    signing_package: &SigningPackage,
+    context: &SigningContext,
+    public_key_package: &PublicKeyPackage,
+) -> Result<(), SignatureError> {
+    let invalid = SignatureError::InvalidPartialSignature {
+        index: identifier.into(),
+    };
+    let commitments = signing_package
+        .commitments
+        .get(&identifier)
+        .ok_or(SignatureError::UnknownSigner)?;
+    let verifying_share = public_key_package
+        .verifying_shares
+        .get(&identifier)
+        .ok_or(SignatureError::UnknownSigner)?;
+
+    let rho = context.binding_factors[&identifier];
+    let lambda = signing_package.lagrange_coefficient(identifier);
+    if G1::generator() * share.share
+        == commitments.hiding
+            + commitments.binding * rho
+            + *verifying_share * (context.challenge * lambda)
+    {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+/// Verifies the signature share of a single signer, which identifies cheating signers.
+pub fn verify_signature_share(
+    identifier: Identifier,
+    share: &SignatureShare,
+    signing_package: &SigningPackage,
+    public_key_package: &PublicKeyPackage,
+) -> Result<(), SignatureError> {
+    let context = SigningContext::new(
+        signing_package,
+        &public_key_package.group_public_key,
+        signing_package.commitments.len() as u16,
+    )?;
+    verify_share(
+        identifier,
+        share,
+        signing_package,
+        &context,
+        public_key_package,
+    )
+}
+
+/// Sums the signature shares into a Schnorr signature under the group public key.
+/// When the result does not verify, every share is checked and the first
+/// invalid one is reported.
+pub fn aggregate(
+    signing_package: &SigningPackage,
+    shares: &BTreeMap<Identifier, SignatureShare>,
+    public_key_package: &PublicKeyPackage,
+) -> Result<SchSign, SignatureError> {
+    if shares.len() != signing_package.commitments.len() {
+        return Err(SignatureError::SignerCountMismatch {
+            expected: signing_package.commitments.len(),
+            found: shares.len(),
+        });
+    }
+    if shares
+        .keys()
+        .any(|identifier| !signing_package.commitments.contains_key(identifier))
+    {
+        return Err(SignatureError::UnknownSigner);
+    }
+
+    let context = SigningContext::new(
+        signing_package,
+        &public_key_package.group_public_key,
+        shares.len() as u16,
+    )?;
+    let signature = SchSign {
+        big_r: context.group_commitment,
+        s: shares.values().map(|share| share.share).sum(),
+    };
+
+    if sch_verify(
+        &public_key_package.group_public_key,
+        signing_package.message,
+        &signature,
+    )
+    .is_err()
+    {
+        for (identifier, share) in shares {
+            verify_share(
+                *identifier,
+                share,
+                signing_package,
+                &context,
+                public_key_package,
+            )?;
+        }
+        return Err(SignatureError::InvalidSignature);
+    }
+
+    Ok(signature)
+}
@@ -1,104 +1,164 @@
 use ark_bn254::{Fr, G1Projective as G1};
 use ark_ec::Group;
-use ark_ff::{BigInteger, PrimeField};
-use ark_std::UniformRand;
-
-use crate::poseidon_hash::sponge::PoseidonSponge;
-
-/// Signature scheme was made using https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
-pub struct SchMuSig {
-    pub(crate) big_r_alice: G1,
-    pub(crate) s_alice: Fr,
-    pub(crate) alice_pub: G1,
-    pub(crate) big_r_bob: G1,
-    pub(crate) s_bob: Fr,
-    pub(crate) bob_pub: G1,
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    UniformRand,
+};
+
+use crate::{
+    error::SignatureError,
+    poseidon_hash::poseidon_hash,
+    signatures::{
+        encoding::point_to_field_elements,
+        schnorr_single::{challenge, sch_verify, Keypair, PublicKey, SchSign},
+        transcript::Transcript,
+        validate_public_key,
+    },
+};
+
+/// Tag of the hash that commits to the list of public keys.
+const KEY_LIST_TAG: &str = "MuSig/keylist";
+/// Protocol name of the transcript that derives key aggregation coefficients.
+const COEFFICIENT_TAG: &str = "MuSig/coefficient";
+
+/// Aggregated public key of a group of signers.
+/// Each key "X_i" is weighted by the coefficient "a_i = H(L, X_i)" where "L" commits
+/// to the whole list of keys, which protects against rogue key attacks.
+#[derive(Clone, Debug)]
+pub struct KeyAggContext {
+    pub(crate) public_keys: Vec<PublicKey>,
+    pub(crate) coefficients: Vec<Fr>,
+    pub(crate) aggregate: PublicKey,
 }
 
-impl SchMuSig {
-    pub fn signature(alice_msg: Fr, bob_msg: Fr) -> SchMuSig {
-        // Random number generator.
-        let mut rng = ark_std::test_rng();
-
-        // Alice's private and public key generation.
-        let alice_priv = Fr::rand(&mut rng);
-        let alice_pub = G1::generator() * alice_priv;
-
-        // Bob's private and public key generation.
-        let bob_priv = Fr::rand(&mut rng);
-        let bob_pub = G1::generator() * bob_priv;
-
-        // Alice chooses a random number "r" and generates "R".
-        let r_alice = Fr::rand(&mut rng);
-        let big_r_alice = G1::generator() * r_alice;
-
-        // Bob chooses a random number "r" and generates "R".
-        let r_bob = Fr::rand(&mut rng);
-        let big_r_bob = G1::generator() * r_bob;
-
-        // To protect against attacks, we choose key prefixed Schnorr signatures which
-        // means that the public key is prefixed to the message in the challenge hash input.
-        // Concatenates "r", "pub key" and "message" separately and hashes them.
-        let alice_pub_fr = Fr::from_le_bytes_mod_order(&alice_pub.x.0.to_bytes_le());
-        let big_r_alice_fr = Fr::from_le_bytes_mod_order(&big_r_alice.x.0.to_bytes_le());
-
-        let bob_pub_fr = Fr::from_le_bytes_mod_order(&bob_pub.x.0.to_bytes_le());
-        let big_r_bob_fr = Fr::from_le_bytes_mod_order(&big_r_bob.x.0.to_bytes_le());
-
-        let mut sponge = PoseidonSponge::new();
-
-        sponge.update(&[big_r_alice_fr, alice_pub_fr, alice_msg]);
-        let e_alice = PoseidonSponge::squeeze(&mut sponge);
-
-        sponge.update(&[big_r_bob_fr, bob_pub_fr, bob_msg]);
-        let e_bob = PoseidonSponge::squeeze(&mut sponge);
-
-        // Calculates "s" value
-        let s_alice = r_alice + (e_alice * alice_priv);
-        let s_bob = r_bob + (e_bob * bob_priv);
-
-        SchMuSig {
-            big_r_alice,
-            s_alice,
-            alice_pub,
-            big_r_bob,
-            s_bob,
-            bob_pub,
+impl KeyAggContext {
+    /// Aggregates the public keys of the signers.
+    pub fn new(public_keys: &[PublicKey]) -> Result<KeyAggContext, SignatureError> {
+        if public_keys.is_empty() {
+            return Err(SignatureError::NoSigners);
+        }
+        for public_key in public_keys {
+            validate_public_key(&public_key.0)?;
         }
-    }
-}
 
-pub fn sch_musig_verify(alice_msg: Fr, bob_msg: Fr, signature: SchMuSig) {
-    // Random number generator.
-    let mut rng = ark_std::test_rng();
+        // Hashes the list of public keys as "L".
+        let key_list: Vec<Fr> = public_keys
+            .iter()
+            .flat_map(|public_key| point_to_field_elements(&public_key.0))
+            .collect();
+        let key_list_hash = poseidon_hash(KEY_LIST_TAG, &key_list);
+
+        // Computes "a_i" for every public key.
+        let coefficients: Vec<Fr> = public_keys
+            .iter()
+            .map(|public_key| {
+                let mut transcript = Transcript::new(COEFFICIENT_TAG);
+                transcript.append_scalar("key list", &key_list_hash);
+                transcript.append_point("public key", &public_key.0);
+                transcript.challenge_scalar("coefficient")
+            })
+            .collect();
+
+        // Computes the aggregated public key "X = sum(a_i * X_i)".
+        let aggregate = public_keys
+            .iter()
+            .zip(&coefficients)
+            .map(|(public_key, a)| public_key.0 * a)
+            .sum::<G1>();
+        validate_public_key(&aggregate)?;
+
+        Ok(KeyAggContext {
+            public_keys: public_keys.to_vec(),
+            coefficients,
+            aggregate: PublicKey(aggregate),
+        })
+    }
 
-    // Generates random integers "a". For safety of adding invalid signatures.
-    let alice_rnd_a = Fr::rand(&mut rng);
-    let bob_rnd_a = Fr::rand(&mut rng);
+    /// Returns the aggregated public key.
+    pub fn aggregate_public_key(&self) -> &PublicKey {
+        &self.aggregate
+    }
 
-    // Concatenates "r", "pub key" and "message" separately and hashes them.
-    let alice_pub_fr = Fr::from_le_bytes_mod_order(&signature.alice_pub.x.0.to_bytes_le());
-    let big_r_alice_fr = Fr::from_le_bytes_mod_order(&signature.big_r_alice.x.0.to_bytes_le());
+    /// Returns the public keys of the signers in the aggregation order.
+    pub fn public_keys(&self) -> &[PublicKey] {
+        &self.public_keys
+    }
 
-    let bob_pub_fr = Fr::from_le_bytes_mod_order(&signature.bob_pub.x.0.to_bytes_le());
-    let big_r_bob_fr = Fr::from_le_bytes_mod_order(&signature.big_r_bob.x.0.to_bytes_le());
+    /// Returns the key aggregation coefficient of the signer.
+    pub fn coefficient(&self, index: usize) -> Fr {
+        self.coefficients[index]
+    }
 
-    let mut sponge = PoseidonSponge::new();
+    /// Returns the position of the public key in the aggregation order.
+    pub fn index_of(&self, public_key: &PublicKey) -> Option<usize> {
+        self.public_keys.iter().position(|key| key == public_key)
+    }
+}
 
-    sponge.update(&[big_r_alice_fr, alice_pub_fr, alice_msg]);
-    let e_v_alice = PoseidonSponge::squeeze(&mut sponge);
+/// Signature scheme was made using https://eprint.iacr.org/2018/068.pdf
+/// The aggregated signature is a single Schnorr signature "(R, s)"
+/// under the aggregated public key.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SchMuSig {
+    pub(crate) big_r: G1,
+    pub(crate) s: Fr,
+}
 
-    sponge.update(&[big_r_bob_fr, bob_pub_fr, bob_msg]);
-    let e_v_bob = PoseidonSponge::squeeze(&mut sponge);
+impl SchMuSig {
+    /// Runs the signing protocol for all signers on the same message in one process.
+    /// Needs the keypairs of every signer, so it is only meant for local use and tests.
+    /// Separate signers run the MuSig2 sessions of [`crate::signatures::schnorr_musig2::FirstRound`]
+    /// and [`crate::signatures::schnorr_musig2::SecondRound`] instead.
+    pub fn signature<R: RngCore + CryptoRng>(
+        signers: &[Keypair],
+        message: Fr,
+        rng: &mut R,
+    ) -> Result<SchMuSig, SignatureError> {
+        let public_keys: Vec<PublicKey> = signers.iter().map(|signer| signer.public).collect();
+        let key_agg = KeyAggContext::new(&public_keys)?;
+
+        // Every signer chooses a random number "r_i" and generates "R_i".
+        let nonces: Vec<Fr> = signers.iter().map(|_| Fr::rand(rng)).collect();
+
+        // Computes the aggregated "R = sum(R_i)".
+        let big_r = G1::generator() * nonces.iter().sum::<Fr>();
+
+        // Computes the challenge for the aggregated public key.
+        let e = challenge(&big_r, &key_agg.aggregate.0, message);
+
+        // Every signer calculates "s_i = r_i + e * a_i * x_i" and the results are summed.
+        let s = signers
+            .iter()
+            .zip(&nonces)
+            .enumerate()
+            .map(|(i, (signer, r))| *r + e * key_agg.coefficient(i) * signer.secret.0)
+            .sum();
+
+        Ok(SchMuSig { big_r, s })
+    }
+}
 
-    // Verifies that the equation holds.
-    if G1::generator() * (alice_rnd_a * signature.s_alice + bob_rnd_a * signature.s_bob)
-        == ((signature.alice_pub * alice_rnd_a * e_v_alice)
-            + (signature.bob_pub * bob_rnd_a * e_v_bob))
-            + (signature.big_r_alice * alice_rnd_a + signature.big_r_bob * bob_rnd_a)
-    {
-        println!("Signature matches. They signed the message.")
-    } else {
-        println!("Invalid Signature!")
+impl From<SchMuSig> for SchSign {
+    fn from(signature: SchMuSig) -> SchSign {
+        SchSign {
+            big_r: signature.big_r,
+            s: signature.s,
+        }
     }
 }
+
+pub fn sch_musig_verify(
+    public_keys: &[PublicKey],
+    message: Fr,
+    signature: &SchMuSig,
+) -> Result<(), SignatureError> {
+    let key_agg = KeyAggContext::new(public_keys)?;
+
+    // Verifies the single Schnorr signature under the aggregated public key.
+    sch_verify(
+        key_agg.aggregate_public_key(),
+        message,
+        &signature.clone().into(),
+    )
+}
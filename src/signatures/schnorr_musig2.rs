@@ -0,0 +1,272 @@
+use ark_bn254::{Fr, G1Projective as G1};
+use ark_ec::Group;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    UniformRand,
+};
+
+use crate::{
+    error::SignatureError,
+    poseidon_hash::poseidon_hash,
+    signatures::{
+        encoding::point_to_field_elements,
+        schnorr_musig::{KeyAggContext, SchMuSig},
+        schnorr_single::{challenge, Keypair},
+        transcript::Transcript,
+        validate_point,
+    },
+};
+
+/// Tag of the hash that derives secret nonces.
+const NONCE_TAG: &str = "MuSig2/nonce";
+/// Protocol name of the transcript that derives the nonce coefficient "b".
+const NONCE_COEFFICIENT_TAG: &str = "MuSig2/noncecoef";
+
+/// Public nonce "(R_1, R_2)" of a signer, sent to all other signers in the first round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PubNonce {
+    pub(crate) big_r1: G1,
+    pub(crate) big_r2: G1,
+}
+
+/// Partial signature "s_i" of a signer, sent to all other signers in the second round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PartialSignature {
+    pub(crate) s: Fr,
+}
+
+/// Secret nonce "(r_1, r_2)" of a signer.
+/// It is neither cloneable nor serializable, and it is consumed by signing
+/// so a nonce can never be used for two signatures.
+struct SecNonce {
+    r1: Fr,
+    r2: Fr,
+}
+
+impl SecNonce {
+    /// Derives two secret nonces from fresh randomness bound to the signer's key pair
+    /// and the message, so a weak random number generator alone does not repeat nonces.
+    fn generate<R: RngCore + CryptoRng>(keypair: &Keypair, message: Fr, rng: &mut R) -> Self {
+        let rand = Fr::rand(rng);
+        let derive = |index: u64| {
+            let inputs = [
+                &[rand, keypair.secret.0][..],
+                &point_to_field_elements(&keypair.public.0),
+                &[message, Fr::from(index)],
+            ]
+            .concat();
+            poseidon_hash(NONCE_TAG, &inputs)
+        };
+
+        SecNonce {
+            r1: derive(1),
+            r2: derive(2),
+        }
+    }
+
+    /// Returns the matching public nonce.
+    fn public_nonce(&self) -> PubNonce {
+        PubNonce {
+            big_r1: G1::generator() * self.r1,
+            big_r2: G1::generator() * self.r2,
+        }
+    }
+}
+
+/// Values shared by all signers once every public nonce is known.
+#[derive(Clone, Debug)]
+struct SessionContext {
+    /// Nonce coefficient "b".
+    b: Fr,
+    /// Final nonce "R = R_1 + b * R_2".
+    big_r: G1,
+    /// Challenge "e = H(R, X, m)".
+    e: Fr,
+}
+
+impl SessionContext {
+    fn new(key_agg: &KeyAggContext, nonces: &[PubNonce], message: Fr) -> Self {
+        // Aggregates the public nonces.
+        let agg_r1: G1 = nonces.iter().map(|nonce| nonce.big_r1).sum();
+        let agg_r2: G1 = nonces.iter().map(|nonce| nonce.big_r2).sum();
+
+        // Hashes the aggregated nonces, the aggregated public key and the message as "b".
+        let mut transcript = Transcript::new(NONCE_COEFFICIENT_TAG);
+        transcript.append_point("R1", &agg_r1);
+        transcript.append_point("R2", &agg_r2);
+        transcript.append_point("aggregate key", &key_agg.aggregate.0);
+        transcript.append_scalar("message", &message);
+        let b = transcript.challenge_scalar("b");
+
+        let big_r = agg_r1 + agg_r2 * b;
+        let e = challenge(&big_r, &key_agg.aggregate.0, message);
+
+        SessionContext { b, big_r, e }
+    }
+}
+
+/// First round of the MuSig2 protocol for one signer.
+/// The signer publishes its public nonce and collects the public nonces of all other signers.
+pub struct FirstRound {
+    key_agg: KeyAggContext,
+    keypair: Keypair,
+    index: usize,
+    message: Fr,
+    sec_nonce: SecNonce,
+    nonces: Vec<Option<PubNonce>>,
+}
+
+impl FirstRound {
+    /// Starts signing the message as a member of the aggregated key.
+    pub fn new<R: RngCore + CryptoRng>(
+        key_agg: KeyAggContext,
+        keypair: Keypair,
+        message: Fr,
+        rng: &mut R,
+    ) -> Result<FirstRound, SignatureError> {
+        let index = key_agg
+            .index_of(&keypair.public)
+            .ok_or(SignatureError::UnknownSigner)?;
+
+        let sec_nonce = SecNonce::generate(&keypair, message, rng);
+        let mut nonces = vec![None; key_agg.public_keys.len()];
+        nonces[index] = Some(sec_nonce.public_nonce());
+
+        Ok(FirstRound {
+            key_agg,
+            keypair,
+            index,
+            message,
+            sec_nonce,
+            nonces,
+        })
+    }
+
+    /// Returns the public nonce to send to the other signers.
+    pub fn our_public_nonce(&self) -> PubNonce {
+        self.nonces[self.index].expect("Own nonce is always present")
+    }
+
+    /// Records the public nonce of the signer at the given index.
+    pub fn receive_nonce(&mut self, index: usize, nonce: PubNonce) -> Result<(), SignatureError> {
+        validate_point(&nonce.big_r1)?;
+        validate_point(&nonce.big_r2)?;
+
+        let slot = self
+            .nonces
+            .get_mut(index)
+            .ok_or(SignatureError::UnknownSigner)?;
+        match slot {
+            Some(existing) if *existing != nonce => Err(SignatureError::DuplicateSigner),
+            _ => {
+                *slot = Some(nonce);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns true when the public nonces of all signers have arrived.
+    pub fn is_complete(&self) -> bool {
+        self.nonces.iter().all(Option::is_some)
+    }
+
+    /// Computes the partial signature and moves to the second round.
+    /// Fails if some public nonces are still missing, which discards the secret nonce
+    /// so the session has to start over.
+    pub fn finalize(self) -> Result<SecondRound, SignatureError> {
+        let nonces: Vec<PubNonce> = self.nonces.iter().flatten().copied().collect();
+        if nonces.len() != self.nonces.len() {
+            return Err(SignatureError::SignerCountMismatch {
+                expected: self.nonces.len(),
+                found: nonces.len(),
+            });
+        }
+
+        let context = SessionContext::new(&self.key_agg, &nonces, self.message);
+
+        // Calculates "s_i = r_1 + b * r_2 + e * a_i * x_i".
+        let a = self.key_agg.coefficient(self.index);
+        let s = self.sec_nonce.r1
+            + context.b * self.sec_nonce.r2
+            + context.e * a * self.keypair.secret.0;
+
+        let mut signatures = vec![None; nonces.len()];
+        signatures[self.index] = Some(PartialSignature { s });
+
+        Ok(SecondRound {
+            key_agg: self.key_agg,
+            index: self.index,
+            nonces,
+            context,
+            signatures,
+        })
+    }
+}
+
+/// Second round of the MuSig2 protocol for one signer.
+/// The signer publishes its partial signature, verifies the partial signatures of all
+/// other signers and aggregates them.
+pub struct SecondRound {
+    key_agg: KeyAggContext,
+    index: usize,
+    nonces: Vec<PubNonce>,
+    context: SessionContext,
+    signatures: Vec<Option<PartialSignature>>,
+}
+
+impl SecondRound {
+    /// Returns the partial signature to send to the other signers.
+    pub fn our_signature(&self) -> PartialSignature {
+        self.signatures[self.index].expect("Own partial signature is always present")
+    }
+
+    /// Verifies and records the partial signature of the signer at the given index.
+    pub fn receive_signature(
+        &mut self,
+        index: usize,
+        signature: PartialSignature,
+    ) -> Result<(), SignatureError> {
+        if index >= self.signatures.len() {
+            return Err(SignatureError::UnknownSigner);
+        }
+        if matches!(self.signatures[index], Some(existing) if existing != signature) {
+            return Err(SignatureError::DuplicateSigner);
+        }
+
+        // Verifies that "s_i * G = R_1 + b * R_2 + e * a_i * X_i".
+        let nonce = &self.nonces[index];
+        let public_key = self.key_agg.public_keys[index].0;
+        let a = self.key_agg.coefficient(index);
+        if G1::generator() * signature.s
+            != nonce.big_r1 + nonce.big_r2 * self.context.b + public_key * (self.context.e * a)
+        {
+            return Err(SignatureError::InvalidPartialSignature { index });
+        }
+
+        self.signatures[index] = Some(signature);
+        Ok(())
+    }
+
+    /// Returns true when the partial signatures of all signers have arrived.
+    pub fn is_complete(&self) -> bool {
+        self.signatures.iter().all(Option::is_some)
+    }
+
+    /// Sums the partial signatures into the aggregated signature.
+    /// Fails if some partial signatures are still missing.
+    pub fn finalize(self) -> Result<SchMuSig, SignatureError> {
+        let signatures: Vec<PartialSignature> = self.signatures.iter().flatten().copied().collect();
+        if signatures.len() != self.signatures.len() {
+            return Err(SignatureError::SignerCountMismatch {
+                expected: self.signatures.len(),
+                found: signatures.len(),
+            });
+        }
+
+        Ok(SchMuSig {
+            big_r: self.context.big_r,
+            s: signatures.iter().map(|signature| signature.s).sum(),
+        })
+    }
+}
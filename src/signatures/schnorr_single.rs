@@ -1,64 +1,266 @@
-use crate::poseidon_hash::sponge::PoseidonSponge;
+use crate::{
+    error::SignatureError,
+    poseidon_hash::{
+        poseidon_hash, poseidon_hash_n,
+        sponge::{PoseidonSponge, SpongeMode},
+    },
+    signatures::{
+        encoding::point_to_field_elements, find_invalid, split_prepared, transcript::Transcript,
+        validate_point, validate_public_key,
+    },
+};
 
-use ark_bn254::{Fr, G1Projective as G1};
-use ark_ec::Group;
-use ark_ff::{BigInteger, PrimeField};
-use ark_std::UniformRand;
+use ark_bn254::{Fr, G1Affine, G1Projective as G1};
+use ark_ec::{CurveGroup, Group, VariableBaseMSM};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    cfg_iter,
+    rand::{CryptoRng, RngCore},
+    UniformRand,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-/// Signature scheme was made using https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
-pub struct SchSign {
-    pub(crate) big_r: G1,
-    pub(crate) s: Fr,
-    pub(crate) alice_pub: G1,
+/// Schnorr private key.
+#[derive(Clone)]
+pub struct SecretKey(pub(crate) Fr);
+
+impl SecretKey {
+    /// Wraps the scalar as a private key.
+    pub fn new(scalar: Fr) -> Self {
+        SecretKey(scalar)
+    }
+
+    /// Derives the public key in group G1.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(G1::generator() * self.0)
+    }
 }
 
-impl SchSign {
-    pub fn signature(message: Fr) -> SchSign {
-        // Random number generator.
-        let mut rng = ark_std::test_rng();
+/// Schnorr public key in group G1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicKey(pub(crate) G1);
 
-        // Alice's private and public key generation.
-        let alice_priv = Fr::rand(&mut rng);
-        let alice_pub = G1::generator() * alice_priv;
+impl PublicKey {
+    /// Returns the public key point.
+    pub fn point(&self) -> G1 {
+        self.0
+    }
+}
+
+/// Schnorr private and public key pair.
+#[derive(Clone)]
+pub struct Keypair {
+    pub(crate) secret: SecretKey,
+    pub(crate) public: PublicKey,
+}
 
-        // Alice chooses a random number "r" and generates "R".
-        let r = Fr::rand(&mut rng);
+impl Keypair {
+    /// Generates a fresh key pair.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Keypair {
+        Keypair::from_secret_key(SecretKey(Fr::rand(rng)))
+    }
+
+    /// Builds the key pair for an existing private key.
+    pub fn from_secret_key(secret: SecretKey) -> Keypair {
+        let public = secret.public_key();
+        Keypair { secret, public }
+    }
+
+    /// Returns the private key.
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.secret
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Signs the message with a nonce drawn from the given random number generator.
+    pub fn sign<R: RngCore + CryptoRng>(&self, message: Fr, rng: &mut R) -> SchSign {
+        // Alice chooses a random number "r".
+        let r = Fr::rand(rng);
+
+        self.sign_with_nonce(r, message)
+    }
+
+    /// Signs the message with a nonce derived from the private key, the message
+    /// and optional auxiliary randomness, following the BIP-340 nonce generation.
+    /// Signing is deterministic when no auxiliary randomness is given.
+    pub fn sign_deterministic(&self, message: Fr, aux_rand: Option<Fr>) -> SchSign {
+        // Masks the private key with the hashed auxiliary randomness.
+        let t = self.secret.0 + poseidon_hash_n(AUX_TAG, [aux_rand.unwrap_or_else(Fr::zero)]);
+
+        // Derives "r" from the masked key, the public key and the message.
+        let inputs = [
+            &[t][..],
+            &point_to_field_elements(&self.public.0),
+            &[message],
+        ]
+        .concat();
+        let r = poseidon_hash(NONCE_TAG, &inputs);
+        // Fails with negligible probability, as in BIP-340.
+        assert!(!r.is_zero(), "derived nonce is zero");
+
+        self.sign_with_nonce(r, message)
+    }
+
+    /// Signs the message with the given nonce "r".
+    fn sign_with_nonce(&self, r: Fr, message: Fr) -> SchSign {
+        // Generates "R".
         let big_r = G1::generator() * r;
 
         // To protect against attacks, we choose key prefixed Schnorr signatures which
         // means that the public key is prefixed to the message in the challenge hash input.
-        // Concatenates "r", "alice pub key" and "message" then hashes them.
-        let alice_pub_fr = Fr::from_le_bytes_mod_order(&alice_pub.x.0.to_bytes_le());
-        let big_r_fr = Fr::from_le_bytes_mod_order(&big_r.x.0.to_bytes_le());
-
-        let mut sponge = PoseidonSponge::new();
-        sponge.update(&[big_r_fr, alice_pub_fr, message]);
-        let e = PoseidonSponge::squeeze(&mut sponge);
+        let e = challenge(&big_r, &self.public.0, message);
 
         // Calculates "s" value
-        let s = r + (e * alice_priv);
+        let s = r + (e * self.secret.0);
 
-        SchSign {
-            big_r,
-            s,
-            alice_pub,
-        }
+        SchSign { big_r, s }
     }
 }
 
-pub fn sch_verify(message: Fr, signature: SchSign) {
-    // Concatenates "r", "alice pub key" and "message" then hashes them.
-    let alice_pub_fr = Fr::from_le_bytes_mod_order(&signature.alice_pub.x.0.to_bytes_le());
-    let big_r_fr = Fr::from_le_bytes_mod_order(&signature.big_r.x.0.to_bytes_le());
+/// Tag of the hash that masks the private key with auxiliary randomness.
+const AUX_TAG: &str = "Schnorr/aux";
+/// Tag of the hash that derives deterministic nonces.
+const NONCE_TAG: &str = "Schnorr/nonce";
+/// Protocol name of the transcript that derives the challenge.
+const CHALLENGE_TAG: &str = "Schnorr/challenge";
 
-    let mut sponge = PoseidonSponge::new();
-    sponge.update(&[big_r_fr, alice_pub_fr, message]);
-    let e_v = PoseidonSponge::squeeze(&mut sponge);
+/// Signature scheme was made using https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+#[derive(Clone, Debug)]
+pub struct SchSign {
+    pub(crate) big_r: G1,
+    pub(crate) s: Fr,
+}
+
+/// Appends "r", "pub key" and "message" to the transcript then derives the challenge.
+/// Points are appended in their canonical encoding.
+pub(crate) fn challenge(big_r: &G1, public_key: &G1, message: Fr) -> Fr {
+    let mut transcript = Transcript::new(CHALLENGE_TAG);
+    transcript.append_point("R", big_r);
+    transcript.append_point("public key", public_key);
+    transcript.append_scalar("message", &message);
+    transcript.challenge_scalar("challenge")
+}
+
+/// Concatenates "r", "pub key" and "message" then hashes them with the compatibility mode,
+/// the way challenges were derived before the sponge got its capacity and the transcript.
+fn compat_challenge(big_r: &G1, public_key: &G1, message: Fr) -> Fr {
+    let mut sponge: PoseidonSponge = PoseidonSponge::with_mode(SpongeMode::Compat);
+    sponge.update(&point_to_field_elements(big_r));
+    sponge.update(&point_to_field_elements(public_key));
+    sponge.update(&[message]);
+    sponge.squeeze()
+}
+
+pub fn sch_verify(
+    public_key: &PublicKey,
+    message: Fr,
+    signature: &SchSign,
+) -> Result<(), SignatureError> {
+    verify_with(public_key, message, signature, challenge)
+}
+
+/// Verifies signatures made by earlier versions, whose challenges are hashed
+/// with [`SpongeMode::Compat`]. New signatures are checked with [`sch_verify`].
+pub fn sch_verify_compat(
+    public_key: &PublicKey,
+    message: Fr,
+    signature: &SchSign,
+) -> Result<(), SignatureError> {
+    verify_with(public_key, message, signature, compat_challenge)
+}
+
+/// Verifies the signature with the given challenge derivation.
+fn verify_with(
+    public_key: &PublicKey,
+    message: Fr,
+    signature: &SchSign,
+    challenge: fn(&G1, &G1, Fr) -> Fr,
+) -> Result<(), SignatureError> {
+    validate_public_key(&public_key.0)?;
+    validate_point(&signature.big_r)?;
+
+    let e_v = challenge(&signature.big_r, &public_key.0, message);
 
     // Verifies that the equation holds.
-    if G1::generator() * signature.s == (signature.alice_pub * e_v) + signature.big_r {
-        println!("Signature matches. Alice signed the message.")
+    if G1::generator() * signature.s == (public_key.0 * e_v) + signature.big_r {
+        Ok(())
     } else {
-        println!("Invalid Signature!")
+        Err(SignatureError::InvalidSignature)
     }
 }
+
+/// Signature prepared for batch verification, with its challenge already computed.
+struct BatchEntry {
+    big_r: G1Affine,
+    public_key: G1Affine,
+    s: Fr,
+    e: Fr,
+}
+
+/// Verifies many signatures of different signers at once.
+/// The equations "s_i * G = R_i + e_i * P_i" are combined with random coefficients "a_i"
+/// into the single multi-scalar multiplication
+/// "(sum(a_i * s_i)) * G - sum(a_i * R_i) - sum(a_i * e_i * P_i) = 0".
+/// When the batch fails, it is split in halves until the invalid signatures are found,
+/// and their indices are returned.
+pub fn batch_verify<R: RngCore + CryptoRng>(
+    signatures: &[(PublicKey, Fr, SchSign)],
+    rng: &mut R,
+) -> Result<(), SignatureError> {
+    // Signatures with malformed points are invalid without taking part in the batch.
+    // Challenges of all signatures are hashed in parallel with the parallel feature.
+    let prepared: Vec<Result<(usize, BatchEntry), usize>> = cfg_iter!(signatures)
+        .enumerate()
+        .map(|(index, (public_key, message, signature))| {
+            validate_public_key(&public_key.0).map_err(|_| index)?;
+            validate_point(&signature.big_r).map_err(|_| index)?;
+            let entry = BatchEntry {
+                big_r: signature.big_r.into_affine(),
+                public_key: public_key.0.into_affine(),
+                s: signature.s,
+                e: challenge(&signature.big_r, &public_key.0, *message),
+            };
+            Ok((index, entry))
+        })
+        .collect();
+    let (entries, mut invalid) = split_prepared(prepared);
+
+    find_invalid(&entries, &mut |batch| check_batch(batch, rng), &mut invalid);
+
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        invalid.sort_unstable();
+        Err(SignatureError::InvalidBatch { indices: invalid })
+    }
+}
+
+/// Checks the combined equation of the entries with one multi-scalar multiplication.
+fn check_batch<R: RngCore + CryptoRng>(entries: &[(usize, BatchEntry)], rng: &mut R) -> bool {
+    let mut bases = Vec::with_capacity(2 * entries.len() + 1);
+    let mut scalars = Vec::with_capacity(2 * entries.len() + 1);
+    let mut generator_scalar = Fr::zero();
+
+    for (_, entry) in entries {
+        // 128-bit coefficients are enough to make a forged batch pass with negligible probability.
+        let a = Fr::from(u128::from(rng.next_u64()) << 64 | u128::from(rng.next_u64()));
+
+        generator_scalar += a * entry.s;
+        bases.push(entry.big_r);
+        scalars.push(-a);
+        bases.push(entry.public_key);
+        scalars.push(-(a * entry.e));
+    }
+    bases.push(G1::generator().into_affine());
+    scalars.push(generator_scalar);
+
+    G1::msm(&bases, &scalars)
+        .expect("Bases and scalars have the same length")
+        .is_zero()
+}
@@ -0,0 +1,116 @@
+use ark_bn254::Fr;
+use ark_ec::CurveGroup;
+
+use crate::{
+    poseidon_hash::{bytes_to_field_elements, sponge::PoseidonSponge},
+    signatures::encoding::point_to_field_elements,
+};
+
+/// Operation tags, absorbed before the label of every operation
+/// so that messages of different types never have the same encoding.
+const SCALAR_OP: u64 = 1;
+const POINT_OP: u64 = 2;
+const BYTES_OP: u64 = 3;
+const CHALLENGE_OP: u64 = 4;
+
+/// Fiat-Shamir transcript of a protocol, built on the Poseidon sponge.
+/// The transcript starts as a tagged hash of the protocol name, and every
+/// message is absorbed with its type, label and length, so that transcripts of
+/// different protocols or with different messages never give the same challenge.
+/// Challenges depend on all messages and challenges before them.
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    sponge: PoseidonSponge,
+}
+
+impl Transcript {
+    /// Starts the transcript of the protocol.
+    pub fn new(protocol_name: &str) -> Self {
+        Transcript {
+            sponge: PoseidonSponge::new_tagged(protocol_name),
+        }
+    }
+
+    /// Appends a scalar with its label.
+    pub fn append_scalar(&mut self, label: &str, scalar: &Fr) {
+        self.append_operation(SCALAR_OP, label);
+        self.sponge.update(&[Fr::from(1u64), *scalar]);
+    }
+
+    /// Appends a point in its canonical encoding with its label.
+    pub fn append_point<C: CurveGroup>(&mut self, label: &str, point: &C) {
+        let elements = point_to_field_elements(point);
+        self.append_operation(POINT_OP, label);
+        self.sponge.update(&[Fr::from(elements.len() as u64)]);
+        self.sponge.update(&elements);
+    }
+
+    /// Appends bytes with their label.
+    /// The bytes are packed into 31 byte chunks after their length.
+    pub fn append_bytes(&mut self, label: &str, bytes: &[u8]) {
+        self.append_operation(BYTES_OP, label);
+        self.sponge.update(&[Fr::from(bytes.len() as u64)]);
+        self.sponge.update(&bytes_to_field_elements(bytes));
+    }
+
+    /// Derives a challenge with its label from the transcript so far.
+    pub fn challenge_scalar(&mut self, label: &str) -> Fr {
+        self.append_operation(CHALLENGE_OP, label);
+        self.sponge.squeeze()
+    }
+
+    /// Absorbs the operation tag and the label with its length.
+    fn append_operation(&mut self, operation: u64, label: &str) {
+        self.sponge
+            .update(&[Fr::from(operation), Fr::from(label.len() as u64)]);
+        self.sponge
+            .update(&bytes_to_field_elements(label.as_bytes()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::G1Projective as G1;
+    use ark_ec::Group;
+
+    /// Derives a challenge from the protocol name and a fixed list of messages.
+    fn challenge(protocol_name: &str, label: &str, bytes: &[u8]) -> Fr {
+        let mut transcript = Transcript::new(protocol_name);
+        transcript.append_point("point", &G1::generator());
+        transcript.append_scalar("scalar", &Fr::from(3u64));
+        transcript.append_bytes(label, bytes);
+        transcript.challenge_scalar("challenge")
+    }
+
+    #[test]
+    fn test_transcript() {
+        let expected = challenge("protocol", "bytes", b"message");
+        assert_eq!(challenge("protocol", "bytes", b"message"), expected);
+
+        // Protocol name, labels and messages are all bound to the challenge.
+        assert_ne!(challenge("other protocol", "bytes", b"message"), expected);
+        assert_ne!(challenge("protocol", "other bytes", b"message"), expected);
+        assert_ne!(challenge("protocol", "bytes", b"other message"), expected);
+
+        // Trailing zero bytes and the split between label and message are not lost.
+        assert_ne!(challenge("protocol", "bytes", b"message\0"), expected);
+        assert_ne!(challenge("protocol", "bytesm", b"essage"), expected);
+
+        // Messages of different types never give the same challenge.
+        let mut scalar = Transcript::new("protocol");
+        scalar.append_scalar("m", &Fr::from(5u64));
+        let mut bytes = Transcript::new("protocol");
+        bytes.append_bytes("m", &[5]);
+        assert_ne!(
+            scalar.challenge_scalar("challenge"),
+            bytes.challenge_scalar("challenge")
+        );
+
+        // Later challenges depend on the earlier ones.
+        let mut transcript = Transcript::new("protocol");
+        let first = transcript.challenge_scalar("challenge");
+        let second = transcript.challenge_scalar("challenge");
+        assert_ne!(first, second);
+    }
+}
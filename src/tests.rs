@@ -1,73 +1,1174 @@
+#[cfg(test)]
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+/// Deterministic cryptographically secure random number generator for tests.
+#[cfg(test)]
+pub(crate) fn test_rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
 #[cfg(test)]
 mod test {
-    use crate::signatures::{
-        bls_musig::{bls_musig_verify, BlsMuSig},
-        bls_single::{bls_verify, BlsSig},
-        schnorr_musig::{sch_musig_verify, SchMuSig},
-        schnorr_single::{sch_verify, SchSign},
+    use super::test_rng;
+    use crate::{
+        error::SignatureError,
+        signatures::{
+            bls_musig::{bls_musig_verify, fast_aggregate_verify, AggregateSignature},
+            bls_single::{self, bls_batch_verify, bls_verify},
+            schnorr_frost::{
+                aggregate, commit, dkg_part1, dkg_part2, dkg_part3, sign, trusted_dealer_keygen,
+                verify_signature_share, DkgRound1Package, Identifier, KeyPackage, SignatureShare,
+                SigningPackage, VssCommitment,
+            },
+            schnorr_musig::{sch_musig_verify, KeyAggContext, SchMuSig},
+            schnorr_musig2::{FirstRound, PartialSignature, PubNonce, SecondRound},
+            schnorr_single::{self, batch_verify, sch_verify, sch_verify_compat, SchSign},
+        },
     };
-    use ark_bn254::Fr;
+    use ark_bn254::{Fq, Fr, G1Affine, G1Projective as G1, G2Projective as G2};
+    use ark_ec::{AffineRepr, CurveGroup, Group};
+    use ark_ff::{MontFp, One, Zero};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     use ark_std::UniformRand;
+    use std::collections::BTreeMap;
 
     #[test]
     fn schnorr_test() {
         // Random number generator.
-        let mut rng = ark_std::test_rng();
+        let mut rng = test_rng();
+
+        // Alice's and Bob's key pairs.
+        let alice = schnorr_single::Keypair::generate(&mut rng);
+        let bob = schnorr_single::Keypair::generate(&mut rng);
 
         // Message that wants to sign.
         let tx_num = Fr::rand(&mut rng);
 
         // Alice signs message.
-        let signature = SchSign::signature(tx_num);
+        let signature = alice.sign(tx_num, &mut rng);
 
         // Bob verifies Alice's signature that signed from herself.
-        sch_verify(tx_num, signature);
+        assert_eq!(sch_verify(alice.public_key(), tx_num, &signature), Ok(()));
+
+        // Alice's key signs another message.
+        let other_tx_num = Fr::rand(&mut rng);
+        let other_signature = alice.sign(other_tx_num, &mut rng);
+        assert_eq!(
+            sch_verify(alice.public_key(), other_tx_num, &other_signature),
+            Ok(())
+        );
+
+        // Signature does not verify for another message.
+        assert_eq!(
+            sch_verify(alice.public_key(), tx_num + Fr::one(), &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Signature does not verify under Bob's key.
+        assert_eq!(
+            sch_verify(bob.public_key(), tx_num, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Tampered "s" value is rejected.
+        let mut tampered = signature.clone();
+        tampered.s += Fr::one();
+        assert_eq!(
+            sch_verify(alice.public_key(), tx_num, &tampered),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Identity public key is rejected.
+        let identity = schnorr_single::PublicKey(G1::zero());
+        assert_eq!(
+            sch_verify(&identity, tx_num, &signature),
+            Err(SignatureError::IdentityPublicKey)
+        );
+
+        // "R" that is not on the curve is rejected.
+        let mut malformed = signature;
+        malformed.big_r = G1::new_unchecked(Fq::one(), Fq::one(), Fq::one());
+        assert_eq!(
+            sch_verify(alice.public_key(), tx_num, &malformed),
+            Err(SignatureError::MalformedPoint)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn schnorr_os_rng_test() {
+        // Operating system backed random number generator.
+        let mut rng = crate::signatures::OsRng;
+
+        let alice = schnorr_single::Keypair::generate(&mut rng);
+        let tx_num = Fr::rand(&mut rng);
+
+        // Signing the same message twice draws fresh nonces.
+        let first = alice.sign(tx_num, &mut rng);
+        let second = alice.sign(tx_num, &mut rng);
+        assert_ne!(first.big_r, second.big_r);
+
+        assert_eq!(sch_verify(alice.public_key(), tx_num, &first), Ok(()));
+        assert_eq!(sch_verify(alice.public_key(), tx_num, &second), Ok(()));
+    }
+
+    #[test]
+    fn schnorr_deterministic_test() {
+        // Fixed private key and message.
+        let alice = schnorr_single::Keypair::from_secret_key(schnorr_single::SecretKey::new(
+            Fr::from(1u64),
+        ));
+        let tx_num = Fr::from(42u64);
+
+        // Signing without auxiliary randomness is deterministic.
+        let signature = alice.sign_deterministic(tx_num, None);
+        let again = alice.sign_deterministic(tx_num, None);
+        assert_eq!(signature.big_r, again.big_r);
+        assert_eq!(signature.s, again.s);
+        assert_eq!(sch_verify(alice.public_key(), tx_num, &signature), Ok(()));
+
+        let expected_r_x: Fq =
+            MontFp!("9110477554668213240076103310932940477135243138631323058785003320623639046518");
+        let expected_s: Fr =
+            MontFp!("5171540292186563037605250712106992615758329264050997565669494170042730393773");
+        assert_eq!(signature.big_r.into_affine().x, expected_r_x);
+        assert_eq!(signature.s, expected_s);
+
+        // Auxiliary randomness changes the nonce.
+        let aux_signature = alice.sign_deterministic(tx_num, Some(Fr::from(7u64)));
+        assert_ne!(aux_signature.big_r, signature.big_r);
+        assert_eq!(
+            sch_verify(alice.public_key(), tx_num, &aux_signature),
+            Ok(())
+        );
+
+        let expected_r_x: Fq =
+            MontFp!("425679807959443894524017900449227918130191032774407490852086130580244614363");
+        let expected_s: Fr =
+            MontFp!("494064985696947307077802443079693145533682836879003763002929501747216764347");
+        assert_eq!(aux_signature.big_r.into_affine().x, expected_r_x);
+        assert_eq!(aux_signature.s, expected_s);
+
+        // Another message gets another nonce.
+        let other_signature = alice.sign_deterministic(tx_num + Fr::one(), None);
+        assert_ne!(other_signature.big_r, signature.big_r);
+    }
+
+    #[test]
+    fn schnorr_compat_test() {
+        // Signature of the message 42 under the private key 1, made before the
+        // sponge got its capacity and the challenge its transcript.
+        let alice = schnorr_single::Keypair::from_secret_key(schnorr_single::SecretKey::new(
+            Fr::from(1u64),
+        ));
+        let tx_num = Fr::from(42u64);
+        let signature = SchSign {
+            big_r: G1Affine::new(
+                MontFp!(
+                    "7064439743405752618972964753243478477028991667483079027688429711623386609643"
+                ),
+                MontFp!(
+                    "16800778237142904322384114490884548388830773826893376123280518150114916319756"
+                ),
+            )
+            .into_group(),
+            s: MontFp!(
+                "1076528558906486819865247570641113011758855654395368351567219538552532477768"
+            ),
+        };
+
+        // Only the compatibility path accepts it.
+        assert_eq!(
+            sch_verify_compat(alice.public_key(), tx_num, &signature),
+            Ok(())
+        );
+        assert_eq!(
+            sch_verify(alice.public_key(), tx_num, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+        assert_eq!(
+            sch_verify_compat(alice.public_key(), tx_num + Fr::one(), &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // New signatures are not accepted by the compatibility path.
+        let signature = alice.sign_deterministic(tx_num, None);
+        assert_eq!(
+            sch_verify_compat(alice.public_key(), tx_num, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn schnorr_batch_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Signatures of many signers over different messages.
+        let mut batch: Vec<(schnorr_single::PublicKey, Fr, schnorr_single::SchSign)> = (0..64)
+            .map(|_| {
+                let signer = schnorr_single::Keypair::generate(&mut rng);
+                let tx_num = Fr::rand(&mut rng);
+                (*signer.public_key(), tx_num, signer.sign(tx_num, &mut rng))
+            })
+            .collect();
+        assert_eq!(batch_verify(&batch, &mut rng), Ok(()));
+        assert_eq!(batch_verify(&[], &mut rng), Ok(()));
+
+        // Invalid signatures are reported by index.
+        batch[3].1 += Fr::one();
+        batch[17].2.s += Fr::one();
+        batch[40].2.big_r = G1::new_unchecked(Fq::one(), Fq::one(), Fq::one());
+        assert_eq!(
+            batch_verify(&batch, &mut rng),
+            Err(SignatureError::InvalidBatch {
+                indices: vec![3, 17, 40]
+            })
+        );
+
+        // Errors that cancel out in the plain sum are caught by the random coefficients.
+        let mut batch: Vec<_> = batch[41..].to_vec();
+        assert_eq!(batch_verify(&batch, &mut rng), Ok(()));
+        batch[0].2.s += Fr::one();
+        batch[1].2.s -= Fr::one();
+        assert_eq!(
+            batch_verify(&batch, &mut rng),
+            Err(SignatureError::InvalidBatch {
+                indices: vec![0, 1]
+            })
+        );
     }
 
     #[test]
     fn schnorr_musig_test() {
         // Random number generator.
-        let mut rng = ark_std::test_rng();
+        let mut rng = test_rng();
+
+        for signer_count in [1, 2, 5] {
+            // Key pairs of the signers.
+            let signers: Vec<schnorr_single::Keypair> = (0..signer_count)
+                .map(|_| schnorr_single::Keypair::generate(&mut rng))
+                .collect();
+            let public_keys: Vec<schnorr_single::PublicKey> =
+                signers.iter().map(|signer| *signer.public_key()).collect();
+
+            // Message that wants to sign.
+            let tx_num = Fr::rand(&mut rng);
+
+            // Signers sign the message together.
+            let signature = SchMuSig::signature(&signers, tx_num, &mut rng).unwrap();
+
+            // Verifies the aggregated signature.
+            assert_eq!(sch_musig_verify(&public_keys, tx_num, &signature), Ok(()));
+
+            // It is a single Schnorr signature under the aggregated public key.
+            let key_agg = KeyAggContext::new(&public_keys).unwrap();
+            assert_eq!(
+                sch_verify(
+                    key_agg.aggregate_public_key(),
+                    tx_num,
+                    &signature.clone().into()
+                ),
+                Ok(())
+            );
+
+            // Signature does not verify for another message.
+            assert_eq!(
+                sch_musig_verify(&public_keys, tx_num + Fr::one(), &signature),
+                Err(SignatureError::InvalidSignature)
+            );
+
+            // Signature does not verify with an extra signer.
+            let mut extended_keys = public_keys.clone();
+            extended_keys.push(*schnorr_single::Keypair::generate(&mut rng).public_key());
+            assert_eq!(
+                sch_musig_verify(&extended_keys, tx_num, &signature),
+                Err(SignatureError::InvalidSignature)
+            );
+
+            // Tampered "s" value is rejected.
+            let mut tampered = signature.clone();
+            tampered.s += Fr::one();
+            assert_eq!(
+                sch_musig_verify(&public_keys, tx_num, &tampered),
+                Err(SignatureError::InvalidSignature)
+            );
+        }
+
+        // Key aggregation weights each key, so the sum of the keys is not the aggregate.
+        let alice = schnorr_single::Keypair::generate(&mut rng);
+        let bob = schnorr_single::Keypair::generate(&mut rng);
+        let key_agg = KeyAggContext::new(&[*alice.public_key(), *bob.public_key()]).unwrap();
+        assert_ne!(
+            key_agg.aggregate_public_key().point(),
+            alice.public_key().point() + bob.public_key().point()
+        );
+
+        // Empty signer set and identity public keys are rejected.
+        let signature =
+            SchMuSig::signature(&[alice.clone(), bob.clone()], Fr::one(), &mut rng).unwrap();
+        assert_eq!(
+            sch_musig_verify(&[], Fr::one(), &signature),
+            Err(SignatureError::NoSigners)
+        );
+        let identity = schnorr_single::PublicKey(G1::zero());
+        assert_eq!(
+            sch_musig_verify(&[*alice.public_key(), identity], Fr::one(), &signature),
+            Err(SignatureError::IdentityPublicKey)
+        );
+    }
+
+    #[test]
+    fn schnorr_musig2_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Key pairs of the signers.
+        let signers: Vec<schnorr_single::Keypair> = (0..3)
+            .map(|_| schnorr_single::Keypair::generate(&mut rng))
+            .collect();
+        let public_keys: Vec<schnorr_single::PublicKey> =
+            signers.iter().map(|signer| *signer.public_key()).collect();
+        let key_agg = KeyAggContext::new(&public_keys).unwrap();
 
         // Message that wants to sign.
-        let alice_tx_num = Fr::rand(&mut rng);
-        let bob_tx_num = Fr::rand(&mut rng);
+        let tx_num = Fr::rand(&mut rng);
 
-        // Alice signs message.
-        let signature = SchMuSig::signature(alice_tx_num, bob_tx_num);
+        // First round, every signer publishes a public nonce.
+        let mut first_rounds: Vec<FirstRound> = signers
+            .iter()
+            .map(|signer| {
+                FirstRound::new(key_agg.clone(), signer.clone(), tx_num, &mut rng).unwrap()
+            })
+            .collect();
+        let nonces: Vec<PubNonce> = first_rounds
+            .iter()
+            .map(FirstRound::our_public_nonce)
+            .collect();
 
-        // Bob verifies Alice's signature that signed from herself.
-        sch_musig_verify(alice_tx_num, bob_tx_num, signature);
+        // Signing before all nonces arrive fails.
+        let early = FirstRound::new(key_agg.clone(), signers[0].clone(), tx_num, &mut rng).unwrap();
+        assert!(!early.is_complete());
+        assert!(matches!(
+            early.finalize(),
+            Err(SignatureError::SignerCountMismatch {
+                expected: 3,
+                found: 1
+            })
+        ));
+
+        // Nonces travel in their serialized form.
+        for round in first_rounds.iter_mut() {
+            for (index, nonce) in nonces.iter().enumerate() {
+                let mut bytes = Vec::new();
+                nonce.serialize_compressed(&mut bytes).unwrap();
+                let received = PubNonce::deserialize_compressed(&bytes[..]).unwrap();
+                round.receive_nonce(index, received).unwrap();
+            }
+            assert!(round.is_complete());
+        }
+
+        // Unknown signers and conflicting nonces are rejected.
+        assert_eq!(
+            first_rounds[0].receive_nonce(3, nonces[0]),
+            Err(SignatureError::UnknownSigner)
+        );
+        assert_eq!(
+            first_rounds[0].receive_nonce(1, nonces[2]),
+            Err(SignatureError::DuplicateSigner)
+        );
+
+        // Second round, every signer publishes a partial signature.
+        let mut second_rounds: Vec<SecondRound> = first_rounds
+            .into_iter()
+            .map(|round| round.finalize().unwrap())
+            .collect();
+        let partials: Vec<PartialSignature> = second_rounds
+            .iter()
+            .map(SecondRound::our_signature)
+            .collect();
+
+        // Invalid partial signature identifies the signer.
+        let mut forged = Vec::new();
+        partials[2].serialize_compressed(&mut forged).unwrap();
+        let forged = PartialSignature::deserialize_compressed(&forged[..]).unwrap();
+        assert_eq!(
+            second_rounds[0].receive_signature(1, forged),
+            Err(SignatureError::InvalidPartialSignature { index: 1 })
+        );
+
+        for round in second_rounds.iter_mut() {
+            for (index, partial) in partials.iter().enumerate() {
+                round.receive_signature(index, *partial).unwrap();
+            }
+            assert!(round.is_complete());
+        }
+
+        // Every signer ends up with the same aggregated signature.
+        let results: Vec<SchMuSig> = second_rounds
+            .into_iter()
+            .map(|round| round.finalize().unwrap())
+            .collect();
+        for signature in &results {
+            assert_eq!(sch_musig_verify(&public_keys, tx_num, signature), Ok(()));
+            assert_eq!(signature.big_r, results[0].big_r);
+            assert_eq!(signature.s, results[0].s);
+        }
+        assert_eq!(
+            sch_musig_verify(&public_keys, tx_num + Fr::one(), &results[0]),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Aggregation before all partial signatures arrive fails.
+        let mut first_rounds: Vec<FirstRound> = signers
+            .iter()
+            .map(|signer| {
+                FirstRound::new(key_agg.clone(), signer.clone(), tx_num, &mut rng).unwrap()
+            })
+            .collect();
+        let nonces: Vec<PubNonce> = first_rounds
+            .iter()
+            .map(FirstRound::our_public_nonce)
+            .collect();
+        for round in first_rounds.iter_mut() {
+            for (index, nonce) in nonces.iter().enumerate() {
+                round.receive_nonce(index, *nonce).unwrap();
+            }
+        }
+        let second_round = first_rounds.remove(0).finalize().unwrap();
+        assert!(matches!(
+            second_round.finalize(),
+            Err(SignatureError::SignerCountMismatch {
+                expected: 3,
+                found: 1
+            })
+        ));
+
+        // Signer outside of the group can not start a session.
+        let outsider = schnorr_single::Keypair::generate(&mut rng);
+        assert!(matches!(
+            FirstRound::new(key_agg, outsider, tx_num, &mut rng),
+            Err(SignatureError::UnknownSigner)
+        ));
+    }
+
+    #[test]
+    fn schnorr_frost_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Trusted dealer splits the group key into 3-of-5 shares.
+        let (shares, public_key_package) = trusted_dealer_keygen(5, 3, &mut rng).unwrap();
+        let key_packages: Vec<KeyPackage> = shares
+            .into_iter()
+            .map(|share| KeyPackage::new(share).unwrap())
+            .collect();
+        let group_public_key = *public_key_package.group_public_key();
+        for key_package in &key_packages {
+            assert_eq!(key_package.group_public_key(), &group_public_key);
+            assert_eq!(
+                public_key_package.verifying_share(key_package.identifier()),
+                Some(key_package.verifying_share())
+            );
+        }
+
+        // Invalid thresholds are rejected.
+        assert!(matches!(
+            trusted_dealer_keygen(3, 4, &mut rng),
+            Err(SignatureError::InvalidThreshold)
+        ));
+        assert!(matches!(
+            trusted_dealer_keygen(3, 1, &mut rng),
+            Err(SignatureError::InvalidThreshold)
+        ));
+
+        // Message that wants to sign.
+        let tx_num = Fr::rand(&mut rng);
+
+        // Any 3 of the 5 participants produce a signature accepted by plain Schnorr verification.
+        for chosen in [[0, 1, 2], [1, 3, 4], [0, 2, 4]] {
+            let signers: Vec<&KeyPackage> = chosen.iter().map(|&i| &key_packages[i]).collect();
+
+            // First round, every signer publishes its nonce commitments.
+            let mut nonces = Vec::new();
+            let mut commitments = BTreeMap::new();
+            for signer in &signers {
+                let (signer_nonces, signer_commitments) = commit(signer, &mut rng);
+                nonces.push(signer_nonces);
+                commitments.insert(signer.identifier(), signer_commitments);
+            }
+            let signing_package = SigningPackage::new(commitments, tx_num);
+
+            // Second round, every signer computes its signature share.
+            let signature_shares: BTreeMap<Identifier, SignatureShare> = signers
+                .iter()
+                .zip(nonces)
+                .map(|(signer, signer_nonces)| {
+                    let share = sign(&signing_package, signer_nonces, signer).unwrap();
+                    (signer.identifier(), share)
+                })
+                .collect();
+            for (identifier, share) in &signature_shares {
+                assert!(verify_signature_share(
+                    *identifier,
+                    share,
+                    &signing_package,
+                    &public_key_package
+                )
+                .is_ok());
+            }
+
+            let signature =
+                aggregate(&signing_package, &signature_shares, &public_key_package).unwrap();
+            assert!(sch_verify(&group_public_key, tx_num, &signature).is_ok());
+            assert!(sch_verify(&group_public_key, tx_num + Fr::one(), &signature).is_err());
+
+            // Invalid signature share identifies the cheating signer.
+            let cheater = signers[1].identifier();
+            let mut forged = signature_shares.clone();
+            forged.get_mut(&cheater).unwrap().share += Fr::one();
+            assert!(matches!(
+                verify_signature_share(
+                    cheater,
+                    &forged[&cheater],
+                    &signing_package,
+                    &public_key_package
+                ),
+                Err(SignatureError::InvalidPartialSignature { .. })
+            ));
+            assert_eq!(
+                aggregate(&signing_package, &forged, &public_key_package).unwrap_err(),
+                SignatureError::InvalidPartialSignature {
+                    index: cheater.into()
+                }
+            );
+        }
+
+        // Fewer signers than the threshold cannot sign.
+        let (nonces, commitments) = commit(&key_packages[0], &mut rng);
+        let (_, other_commitments) = commit(&key_packages[1], &mut rng);
+        let signing_package = SigningPackage::new(
+            BTreeMap::from([
+                (key_packages[0].identifier(), commitments),
+                (key_packages[1].identifier(), other_commitments),
+            ]),
+            tx_num,
+        );
+        assert!(matches!(
+            sign(&signing_package, nonces, &key_packages[0]),
+            Err(SignatureError::SignerCountMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+
+        // Nonces that do not match the signing package are refused.
+        let (nonces, _) = commit(&key_packages[0], &mut rng);
+        let (_, third_commitments) = commit(&key_packages[2], &mut rng);
+        let mut commitments_map = signing_package.commitments.clone();
+        commitments_map.insert(key_packages[2].identifier(), third_commitments);
+        let signing_package = SigningPackage::new(commitments_map, tx_num);
+        assert!(matches!(
+            sign(&signing_package, nonces, &key_packages[0]),
+            Err(SignatureError::IncorrectCommitment)
+        ));
+
+        // Tampered secret share is detected against the dealer's commitment.
+        let (mut shares, _) = trusted_dealer_keygen(3, 2, &mut rng).unwrap();
+        shares[1].signing_share += Fr::one();
+        assert!(matches!(
+            KeyPackage::new(shares[1].clone()),
+            Err(SignatureError::InvalidSecretShare { index: 2 })
+        ));
+
+        // Identifier zero is refused when deserializing.
+        let mut bytes = Vec::new();
+        0u16.serialize_compressed(&mut bytes).unwrap();
+        assert!(Identifier::deserialize_compressed(&bytes[..]).is_err());
+        assert!(Identifier::deserialize_compressed_unchecked(&bytes[..]).is_err());
+        let mut bytes = Vec::new();
+        1u16.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(
+            Identifier::deserialize_compressed(&bytes[..]).ok(),
+            Identifier::new(1)
+        );
+
+        // Commitments with fewer than 2 coefficients are refused.
+        let mut share = shares[0].clone();
+        share.signing_share = Fr::zero();
+        share.commitment = VssCommitment(Vec::new());
+        assert!(matches!(
+            KeyPackage::new(share.clone()),
+            Err(SignatureError::InvalidThreshold)
+        ));
+        share.commitment = VssCommitment(vec![G1::zero()]);
+        assert!(matches!(
+            KeyPackage::new(share),
+            Err(SignatureError::InvalidThreshold)
+        ));
+    }
+
+    #[test]
+    fn schnorr_frost_dkg_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+        let identifiers: Vec<Identifier> = (1..=3).map(|i| Identifier::new(i).unwrap()).collect();
+        assert!(Identifier::new(0).is_none());
+
+        // First round, every participant broadcasts its commitment and proof.
+        let mut round1_secrets = Vec::new();
+        let mut round1_packages = BTreeMap::new();
+        for identifier in &identifiers {
+            let (secret, package) = dkg_part1(*identifier, 3, 2, &mut rng).unwrap();
+            round1_secrets.push(secret);
+            round1_packages.insert(*identifier, package);
+        }
+        let received_round1 = |own: Identifier| -> BTreeMap<Identifier, DkgRound1Package> {
+            round1_packages
+                .iter()
+                .filter(|(identifier, _)| **identifier != own)
+                .map(|(identifier, package)| (*identifier, package.clone()))
+                .collect()
+        };
+
+        // Invalid proof of knowledge identifies the participant.
+        let (forged_secret, _) = dkg_part1(identifiers[0], 3, 2, &mut rng).unwrap();
+        let mut forged = received_round1(identifiers[0]);
+        forged.get_mut(&identifiers[1]).unwrap().proof_mu += Fr::one();
+        assert!(matches!(
+            dkg_part2(forged_secret, &forged),
+            Err(SignatureError::InvalidProofOfKnowledge { index: 2 })
+        ));
+
+        // Second round, every participant sends a secret share to each other participant.
+        let mut round2_secrets = Vec::new();
+        let mut round2_outgoing = BTreeMap::new();
+        for (identifier, secret) in identifiers.iter().zip(round1_secrets) {
+            let (secret, packages) = dkg_part2(secret, &received_round1(*identifier)).unwrap();
+            round2_secrets.push(secret);
+            round2_outgoing.insert(*identifier, packages);
+        }
+
+        // Final round, every participant verifies its shares and derives its keys.
+        let mut key_packages = Vec::new();
+        let mut public_key_packages = Vec::new();
+        for (identifier, secret) in identifiers.iter().zip(&round2_secrets) {
+            let received: BTreeMap<_, _> = round2_outgoing
+                .iter()
+                .map(|(sender, packages)| (*sender, packages.get(identifier)))
+                .filter_map(|(sender, package)| package.map(|package| (sender, package.clone())))
+                .collect();
+
+            // Tampered share is detected.
+            let mut tampered = received.clone();
+            let sender = *tampered.keys().next().unwrap();
+            tampered.get_mut(&sender).unwrap().signing_share += Fr::one();
+            assert!(matches!(
+                dkg_part3(secret, &tampered),
+                Err(SignatureError::InvalidSecretShare { .. })
+            ));
+
+            let (key_package, public_key_package) = dkg_part3(secret, &received).unwrap();
+            key_packages.push(key_package);
+            public_key_packages.push(public_key_package);
+        }
+
+        // All participants agree on the group public key.
+        let group_public_key = *public_key_packages[0].group_public_key();
+        for (key_package, public_key_package) in key_packages.iter().zip(&public_key_packages) {
+            assert_eq!(public_key_package.group_public_key(), &group_public_key);
+            assert_eq!(key_package.group_public_key(), &group_public_key);
+        }
+
+        // Two of the three participants sign.
+        let tx_num = Fr::rand(&mut rng);
+        let (nonces1, commitments1) = commit(&key_packages[0], &mut rng);
+        let (nonces3, commitments3) = commit(&key_packages[2], &mut rng);
+        let signing_package = SigningPackage::new(
+            BTreeMap::from([
+                (identifiers[0], commitments1),
+                (identifiers[2], commitments3),
+            ]),
+            tx_num,
+        );
+        let signature_shares = BTreeMap::from([
+            (
+                identifiers[0],
+                sign(&signing_package, nonces1, &key_packages[0]).unwrap(),
+            ),
+            (
+                identifiers[2],
+                sign(&signing_package, nonces3, &key_packages[2]).unwrap(),
+            ),
+        ]);
+        let signature =
+            aggregate(&signing_package, &signature_shares, &public_key_packages[1]).unwrap();
+        assert!(sch_verify(&group_public_key, tx_num, &signature).is_ok());
+
+        // Public messages travel in their serialized form.
+        let mut bytes = Vec::new();
+        signing_package.serialize_compressed(&mut bytes).unwrap();
+        let received = SigningPackage::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(received.commitments, signing_package.commitments);
     }
 
     #[test]
     fn bls_test() {
         // Random number generator.
-        let mut rng = ark_std::test_rng();
+        let mut rng = test_rng();
+
+        // Alice's and Bob's key pairs.
+        let alice: bls_single::Keypair = bls_single::Keypair::generate(&mut rng);
+        let bob: bls_single::Keypair = bls_single::Keypair::generate(&mut rng);
 
         // Message that wants to sign.
         let tx_num = Fr::rand(&mut rng);
 
         // Alice signs message.
-        let signature = BlsSig::sign(tx_num);
+        let signature = alice.sign(tx_num);
 
         // Bob verifies Alice's signature that signed from herself.
-        bls_verify(tx_num, signature);
+        assert_eq!(bls_verify(alice.public_key(), tx_num, &signature), Ok(()));
+
+        // Signature does not verify for another message.
+        assert_eq!(
+            bls_verify(alice.public_key(), tx_num + Fr::one(), &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Signature does not verify under Bob's key.
+        assert_eq!(
+            bls_verify(bob.public_key(), tx_num, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Identity public key is rejected.
+        let identity = bls_single::PublicKey(G2::zero());
+        assert_eq!(
+            bls_verify(&identity, tx_num, &signature),
+            Err(SignatureError::IdentityPublicKey)
+        );
+
+        // Signature that is not on the curve is rejected.
+        let mut malformed = signature;
+        malformed.signature = G1::new_unchecked(Fq::one(), Fq::one(), Fq::one());
+        assert_eq!(
+            bls_verify(alice.public_key(), tx_num, &malformed),
+            Err(SignatureError::MalformedPoint)
+        );
+    }
+
+    #[test]
+    fn bls_batch_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Signatures of many signers over different messages.
+        let mut batch: Vec<(bls_single::PublicKey, Fr, bls_single::BlsSig)> = (0..16)
+            .map(|_| {
+                let signer: bls_single::Keypair = bls_single::Keypair::generate(&mut rng);
+                let tx_num = Fr::rand(&mut rng);
+                (*signer.public_key(), tx_num, signer.sign(tx_num))
+            })
+            .collect();
+        assert_eq!(bls_batch_verify(&batch, &mut rng), Ok(()));
+
+        // Invalid signatures are reported by index.
+        batch[2].1 += Fr::one();
+        batch[9].2.signature = G1::new_unchecked(Fq::one(), Fq::one(), Fq::one());
+        batch[13].0 = batch[12].0;
+        assert_eq!(
+            bls_batch_verify(&batch, &mut rng),
+            Err(SignatureError::InvalidBatch {
+                indices: vec![2, 9, 13]
+            })
+        );
+
+        // Errors that cancel out in the plain sum are caught by the random coefficients.
+        let mut batch: Vec<_> = batch[..2].to_vec();
+        let delta = G1::generator();
+        batch[0].2.signature += delta;
+        batch[1].2.signature -= delta;
+        assert_eq!(
+            bls_batch_verify(&batch, &mut rng),
+            Err(SignatureError::InvalidBatch {
+                indices: vec![0, 1]
+            })
+        );
+
+        // Batches of the min-pk variant.
+        let batch: Vec<(
+            bls_single::PublicKey<bls_single::MinPk>,
+            Fr,
+            bls_single::BlsSig<bls_single::BasicScheme, bls_single::MinPk>,
+        )> = (0..4)
+            .map(|_| {
+                let signer: bls_single::Keypair<bls_single::MinPk> =
+                    bls_single::Keypair::generate(&mut rng);
+                let tx_num = Fr::rand(&mut rng);
+                (*signer.public_key(), tx_num, signer.sign(tx_num))
+            })
+            .collect();
+        assert_eq!(bls_batch_verify(&batch, &mut rng), Ok(()));
     }
 
     #[test]
     fn bls_musig_test() {
         // Random number generator.
-        let mut rng = ark_std::test_rng();
+        let mut rng = test_rng();
 
-        // Message that wants to sign.
-        let alice_tx_num = Fr::rand(&mut rng);
-        let bob_tx_num = Fr::rand(&mut rng);
+        // Key pairs and messages of the signers.
+        let signers: Vec<bls_single::Keypair> = (0..5)
+            .map(|_| bls_single::Keypair::generate(&mut rng))
+            .collect();
+        let tx_nums: Vec<Fr> = signers.iter().map(|_| Fr::rand(&mut rng)).collect();
+        let pairs: Vec<(bls_single::PublicKey, Fr)> = signers
+            .iter()
+            .zip(&tx_nums)
+            .map(|(signer, tx_num)| (*signer.public_key(), *tx_num))
+            .collect();
 
-        // Alice signs message.
-        let signature = BlsMuSig::sign(alice_tx_num, bob_tx_num);
+        // Every signer signs its message.
+        let signatures: Vec<bls_single::BlsSig> = signers
+            .iter()
+            .zip(&tx_nums)
+            .map(|(signer, tx_num)| signer.sign(*tx_num))
+            .collect();
 
-        // Bob verifies Alice's signature that signed from herself.
-        bls_musig_verify(alice_tx_num, bob_tx_num, signature);
+        // Aggregates any number of signatures.
+        for n in [1, 2, 5] {
+            let signature = AggregateSignature::aggregate(&signatures[..n]).unwrap();
+            assert_eq!(bls_musig_verify(&pairs[..n], &signature), Ok(()));
+        }
+        assert_eq!(
+            AggregateSignature::<bls_single::BasicScheme>::aggregate(&[]),
+            Err(SignatureError::NoSigners)
+        );
+        assert_eq!(
+            bls_musig_verify(&[], &AggregateSignature::<bls_single::BasicScheme>::new()),
+            Err(SignatureError::NoSigners)
+        );
+
+        // Incremental aggregation gives the same signature.
+        let mut signature = AggregateSignature::new();
+        for single in &signatures {
+            signature.add(single);
+        }
+        assert_eq!(
+            signature,
+            AggregateSignature::aggregate(&signatures).unwrap()
+        );
+        assert_eq!(bls_musig_verify(&pairs, &signature), Ok(()));
+
+        // Aggregate travels in its serialized form.
+        let mut bytes = Vec::new();
+        signature.serialize_compressed(&mut bytes).unwrap();
+        let received: AggregateSignature =
+            AggregateSignature::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(bls_musig_verify(&pairs, &received), Ok(()));
+
+        // Swapped messages are rejected.
+        let mut swapped = pairs.clone();
+        swapped[0].1 = tx_nums[1];
+        swapped[1].1 = tx_nums[0];
+        assert_eq!(
+            bls_musig_verify(&swapped, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Missing signer is rejected.
+        assert_eq!(
+            bls_musig_verify(&pairs[..4], &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Repeated messages are rejected in the basic scheme, even for a valid aggregate.
+        let same: Vec<bls_single::BlsSig> = signers[..2]
+            .iter()
+            .map(|signer| signer.sign(tx_nums[0]))
+            .collect();
+        let same_pairs: Vec<(bls_single::PublicKey, Fr)> = signers[..2]
+            .iter()
+            .map(|signer| (*signer.public_key(), tx_nums[0]))
+            .collect();
+        assert_eq!(
+            bls_musig_verify(&same_pairs, &AggregateSignature::aggregate(&same).unwrap()),
+            Err(SignatureError::DuplicateMessage)
+        );
+
+        // Rogue key "pub_key_r = x * g2 - pub_key_0" would sign alone for both keys
+        // over the same message, which the distinct message check prevents.
+        let x = Fr::rand(&mut rng);
+        let rogue = bls_single::PublicKey(G2::generator() * x - signers[0].public_key().point());
+        let attacker: bls_single::Keypair =
+            bls_single::Keypair::from_secret_key(bls_single::SecretKey::new(x));
+        let forged = AggregateSignature::aggregate(&[attacker.sign(tx_nums[0])]).unwrap();
+        assert_eq!(
+            bls_musig_verify(
+                &[(*signers[0].public_key(), tx_nums[0]), (rogue, tx_nums[0])],
+                &forged
+            ),
+            Err(SignatureError::DuplicateMessage)
+        );
+
+        // Identity public key is rejected.
+        let mut identity = pairs.clone();
+        identity[0].0 = bls_single::PublicKey(G2::zero());
+        assert_eq!(
+            bls_musig_verify(&identity, &signature),
+            Err(SignatureError::IdentityPublicKey)
+        );
+    }
+
+    #[test]
+    fn bls_pop_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Key pairs of the signers and their proofs of possession.
+        let signers: Vec<bls_single::Keypair> = (0..3)
+            .map(|_| bls_single::Keypair::generate(&mut rng))
+            .collect();
+        let proofs: Vec<bls_single::ProofOfPossession> = signers
+            .iter()
+            .map(bls_single::Keypair::prove_possession)
+            .collect();
+
+        // Proofs travel in their serialized form and are verified once per key.
+        let verified: Vec<bls_single::VerifiedPublicKey> = signers
+            .iter()
+            .zip(&proofs)
+            .map(|(signer, proof)| {
+                let mut bytes = Vec::new();
+                proof.serialize_compressed(&mut bytes).unwrap();
+                let received =
+                    bls_single::ProofOfPossession::deserialize_compressed(&bytes[..]).unwrap();
+                signer.public_key().verify_pop(&received).unwrap()
+            })
+            .collect();
+
+        // Proof of another key is rejected.
+        assert_eq!(
+            signers[0].public_key().verify_pop(&proofs[1]),
+            Err(SignatureError::InvalidProofOfPossession)
+        );
+
+        // Every signer signs the same message in the proof of possession scheme.
+        let tx_num = Fr::rand(&mut rng);
+        let signatures: Vec<bls_single::BlsSig<bls_single::PopScheme>> = signers
+            .iter()
+            .map(|signer| signer.sign_with(tx_num))
+            .collect();
+        assert_eq!(
+            bls_verify(signers[0].public_key(), tx_num, &signatures[0]),
+            Ok(())
+        );
+
+        let signature = AggregateSignature::aggregate(&signatures).unwrap();
+        assert_eq!(fast_aggregate_verify(&verified, tx_num, &signature), Ok(()));
+        assert_eq!(
+            fast_aggregate_verify(&verified, tx_num + Fr::one(), &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+        assert_eq!(
+            fast_aggregate_verify(&verified[..2], tx_num, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Signatures are bound to their scheme, a basic signature does not verify as one
+        // of the proof of possession scheme.
+        let basic = signers[0].sign(tx_num);
+        let relabeled = bls_single::BlsSig::<bls_single::PopScheme> {
+            signature: basic.signature,
+            ciphersuite: Default::default(),
+        };
+        assert_eq!(
+            bls_verify(signers[0].public_key(), tx_num, &relabeled),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Rogue key "pub_key_r = x * g2 - pub_key_0" lets the attacker sign alone for both keys.
+        let x = Fr::rand(&mut rng);
+        let rogue = bls_single::PublicKey(G2::generator() * x - signers[0].public_key().point());
+        let forged = bls_single::Keypair::from_secret_key(bls_single::SecretKey::new(x))
+            .sign_with::<bls_single::PopScheme>(tx_num);
+        let forged = AggregateSignature::aggregate(&[forged]).unwrap();
+        let unchecked = [
+            bls_single::VerifiedPublicKey(*signers[0].public_key()),
+            bls_single::VerifiedPublicKey(rogue),
+        ];
+        assert_eq!(fast_aggregate_verify(&unchecked, tx_num, &forged), Ok(()));
+
+        // The attacker can not prove possession of the rogue key.
+        let attempt =
+            bls_single::Keypair::from_secret_key(bls_single::SecretKey::new(x)).prove_possession();
+        assert_eq!(
+            rogue.verify_pop(&attempt),
+            Err(SignatureError::InvalidProofOfPossession)
+        );
+    }
+
+    #[test]
+    fn bls_aug_test() {
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Key pairs of the signers, shared with the other schemes.
+        let signers: Vec<bls_single::Keypair> = (0..3)
+            .map(|_| bls_single::Keypair::generate(&mut rng))
+            .collect();
+
+        // Every signer signs the same message, augmented with its public key.
+        let tx_num = Fr::rand(&mut rng);
+        let signatures: Vec<bls_single::BlsSig<bls_single::AugScheme>> = signers
+            .iter()
+            .map(|signer| signer.sign_with(tx_num))
+            .collect();
+        assert_eq!(
+            bls_verify(signers[1].public_key(), tx_num, &signatures[1]),
+            Ok(())
+        );
+
+        // Augmented signature is not valid under another public key or in the basic scheme.
+        assert_eq!(
+            bls_verify(signers[0].public_key(), tx_num, &signatures[1]),
+            Err(SignatureError::InvalidSignature)
+        );
+        let relabeled = bls_single::BlsSig::<bls_single::BasicScheme> {
+            signature: signatures[1].signature,
+            ciphersuite: Default::default(),
+        };
+        assert_eq!(
+            bls_verify(signers[1].public_key(), tx_num, &relabeled),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Aggregate verification over identical messages with uncertified keys.
+        let pairs: Vec<(bls_single::PublicKey, Fr)> = signers
+            .iter()
+            .map(|signer| (*signer.public_key(), tx_num))
+            .collect();
+        let signature = AggregateSignature::aggregate(&signatures).unwrap();
+        assert_eq!(bls_musig_verify(&pairs, &signature), Ok(()));
+        assert_eq!(
+            bls_musig_verify(&pairs[..2], &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Rogue key "pub_key_r = x * g2 - pub_key_0" does not let the attacker sign for both keys,
+        // since each key hashes the message differently.
+        let x = Fr::rand(&mut rng);
+        let rogue = bls_single::PublicKey(G2::generator() * x - signers[0].public_key().point());
+        let forged = bls_single::Keypair::from_secret_key(bls_single::SecretKey::new(x))
+            .sign_with::<bls_single::AugScheme>(tx_num);
+        let forged = AggregateSignature::aggregate(&[forged]).unwrap();
+        assert_eq!(
+            bls_musig_verify(
+                &[(*signers[0].public_key(), tx_num), (rogue, tx_num)],
+                &forged
+            ),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Multi-message aggregation in the proof of possession scheme needs verified keys.
+        let verified: Vec<(bls_single::VerifiedPublicKey, Fr)> = signers
+            .iter()
+            .map(|signer| {
+                let proof = signer.prove_possession();
+                (signer.public_key().verify_pop(&proof).unwrap(), tx_num)
+            })
+            .collect();
+        let signatures: Vec<bls_single::BlsSig<bls_single::PopScheme>> = signers
+            .iter()
+            .map(|signer| signer.sign_with(tx_num))
+            .collect();
+        let signature = AggregateSignature::aggregate(&signatures).unwrap();
+        assert_eq!(bls_musig_verify(&verified, &signature), Ok(()));
+    }
+
+    #[test]
+    fn bls_min_pk_test() {
+        use bls_single::{BlsVariant, MinPk, MinSig};
+
+        // Random number generator.
+        let mut rng = test_rng();
+
+        // Key pairs with public keys in G1 and signatures in G2.
+        let signers: Vec<bls_single::Keypair<MinPk>> = (0..3)
+            .map(|_| bls_single::Keypair::generate(&mut rng))
+            .collect();
+        let tx_num = Fr::rand(&mut rng);
+
+        // Single signature.
+        let signature = signers[0].sign(tx_num);
+        assert_eq!(
+            bls_verify(signers[0].public_key(), tx_num, &signature),
+            Ok(())
+        );
+        assert_eq!(
+            bls_verify(signers[1].public_key(), tx_num, &signature),
+            Err(SignatureError::InvalidSignature)
+        );
+
+        // Aggregation over distinct messages.
+        let tx_nums: Vec<Fr> = signers.iter().map(|_| Fr::rand(&mut rng)).collect();
+        let signatures: Vec<bls_single::BlsSig<bls_single::BasicScheme, MinPk>> = signers
+            .iter()
+            .zip(&tx_nums)
+            .map(|(signer, tx_num)| signer.sign(*tx_num))
+            .collect();
+        let pairs: Vec<(bls_single::PublicKey<MinPk>, Fr)> = signers
+            .iter()
+            .zip(&tx_nums)
+            .map(|(signer, tx_num)| (*signer.public_key(), *tx_num))
+            .collect();
+        let aggregate = AggregateSignature::aggregate(&signatures).unwrap();
+        assert_eq!(bls_musig_verify(&pairs, &aggregate), Ok(()));
+
+        // Same-message aggregation with proofs of possession.
+        let verified: Vec<bls_single::VerifiedPublicKey<MinPk>> = signers
+            .iter()
+            .map(|signer| {
+                let proof = signer.prove_possession();
+                signer.public_key().verify_pop(&proof).unwrap()
+            })
+            .collect();
+        let signatures: Vec<bls_single::BlsSig<bls_single::PopScheme, MinPk>> = signers
+            .iter()
+            .map(|signer| signer.sign_with(tx_num))
+            .collect();
+        let aggregate = AggregateSignature::aggregate(&signatures).unwrap();
+        assert_eq!(fast_aggregate_verify(&verified, tx_num, &aggregate), Ok(()));
+
+        // Same private key in the min-sig variant.
+        let secret = signers[0].secret_key().clone();
+        let min_sig: bls_single::Keypair<MinSig> = bls_single::Keypair::from_secret_key(secret);
+
+        // Serialized sizes follow the groups of the variant.
+        assert_eq!(
+            signers[0].public_key().compressed_size(),
+            MinPk::PUBLIC_KEY_SIZE
+        );
+        assert_eq!(signature.compressed_size(), MinPk::SIGNATURE_SIZE);
+        assert_eq!(
+            min_sig.public_key().compressed_size(),
+            MinSig::PUBLIC_KEY_SIZE
+        );
+        assert_eq!(
+            min_sig.sign(tx_num).compressed_size(),
+            MinSig::SIGNATURE_SIZE
+        );
+        assert_eq!(MinPk::PUBLIC_KEY_SIZE, MinSig::SIGNATURE_SIZE);
+        assert_eq!(MinPk::SIGNATURE_SIZE, MinSig::PUBLIC_KEY_SIZE);
+
+        // Deserialized signature verifies.
+        let mut bytes = Vec::new();
+        signature.serialize_compressed(&mut bytes).unwrap();
+        let received: bls_single::BlsSig<bls_single::BasicScheme, MinPk> =
+            bls_single::BlsSig::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(
+            bls_verify(signers[0].public_key(), tx_num, &received),
+            Ok(())
+        );
     }
 }